@@ -0,0 +1,91 @@
+use std::error::Error;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+macro_rules! err {
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
+}
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+// where the puzzle inputs and the private leaderboards come from
+pub trait Fetcher {
+    fn input(&self, year: u16, day: u8) -> Result<String>;
+    // the json export of a private leaderboard, by the id of its owner
+    fn leaderboard(&self, year: u16, id: u64) -> Result<String>;
+}
+
+// the adventofcode.com input of the account whose session cookie is given, through curl
+#[derive(Debug, Clone)]
+pub struct Web {
+    pub session: String,
+}
+
+impl Web {
+    fn get(&self, url: &str) -> Result<String> {
+        // the cookie header goes through stdin, on the command line any local user could read
+        // the session from `ps`
+        let mut curl = Command::new("curl")
+            .args(["--silent", "--show-error", "--fail", "--header", "@-"])
+            .arg(url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = curl.stdin.take() {
+            writeln!(stdin, "Cookie: session={}", self.session)?;
+        }
+        let output = curl.wait_with_output()?;
+        if !output.status.success() {
+            return err!(
+                "unable to fetch {url}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8(output.stdout)?)
+    }
+}
+
+impl Fetcher for Web {
+    fn input(&self, year: u16, day: u8) -> Result<String> {
+        self.get(&format!("https://adventofcode.com/{year}/day/{day}/input"))
+    }
+
+    fn leaderboard(&self, year: u16, id: u64) -> Result<String> {
+        self.get(&format!(
+            "https://adventofcode.com/{year}/leaderboard/private/view/{id}.json"
+        ))
+    }
+}
+
+// saved inputs laid out as `<dir>/<year>/<day>.txt` and leaderboards as
+// `<dir>/<year>/leaderboard/<id>.json`, for working offline and for tests
+#[derive(Debug, Clone)]
+pub struct Saved {
+    pub dir: PathBuf,
+}
+
+impl Saved {
+    fn read(&self, path: &Path) -> Result<String> {
+        let path = self.dir.join(path);
+        match std::fs::read_to_string(&path) {
+            Ok(input) => Ok(input),
+            Err(e) => err!("unable to read {}: {e}", path.display()),
+        }
+    }
+}
+
+impl Fetcher for Saved {
+    fn input(&self, year: u16, day: u8) -> Result<String> {
+        self.read(&Path::new(&year.to_string()).join(format!("{day}.txt")))
+    }
+
+    fn leaderboard(&self, year: u16, id: u64) -> Result<String> {
+        self.read(
+            &Path::new(&year.to_string())
+                .join("leaderboard")
+                .join(format!("{id}.json")),
+        )
+    }
+}
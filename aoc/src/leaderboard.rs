@@ -0,0 +1,571 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer};
+use serde_json::json;
+
+macro_rules! err {
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
+}
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+// (get_star_ts, star_index), the star index breaks ties between equal timestamps
+type Star = (u64, u64);
+
+// the leaderboard api has encoded some ids and events as strings
+fn number_or_string<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Number(u64),
+        String(String),
+    }
+    match Raw::deserialize(d)? {
+        Raw::Number(n) => Ok(n),
+        Raw::String(s) => s.parse().map_err(de::Error::custom),
+    }
+}
+
+// the json export as it is, see `Leaderboard` for the checked form
+#[derive(Debug, Deserialize)]
+struct RawLeaderboard {
+    #[serde(deserialize_with = "number_or_string")]
+    event: u64,
+    #[serde(default, deserialize_with = "number_or_string")]
+    owner_id: u64,
+    members: BTreeMap<String, RawMember>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMember {
+    #[serde(deserialize_with = "number_or_string")]
+    id: u64,
+    name: Option<String>,
+    #[serde(default)]
+    stars: u64,
+    #[serde(default)]
+    local_score: u64,
+    #[serde(default)]
+    completion_day_level: BTreeMap<u32, BTreeMap<usize, RawStar>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawStar {
+    get_star_ts: u64,
+    #[serde(default)]
+    star_index: u64,
+}
+
+#[derive(Debug)]
+struct Member {
+    id: u64,
+    name: Option<String>,
+    stars: u64,
+    local_score: u64,
+    days: BTreeMap<u32, [Option<Star>; 2]>,
+}
+
+impl TryFrom<RawMember> for Member {
+    type Error = Box<dyn Error>;
+
+    fn try_from(raw: RawMember) -> Result<Self> {
+        let id = raw.id;
+        let mut days = BTreeMap::new();
+        for (day, parts) in raw.completion_day_level {
+            let mut stars = [None; 2];
+            for (part, star) in parts {
+                if !(1..=2).contains(&part) {
+                    return err!("member {id}: day {day} has an invalid part {part}");
+                }
+                stars[part - 1] = Some((star.get_star_ts, star.star_index));
+            }
+            days.insert(day, stars);
+        }
+        Ok(Member {
+            id,
+            name: raw.name,
+            stars: raw.stars,
+            local_score: raw.local_score,
+            days,
+        })
+    }
+}
+
+impl Member {
+    fn display_name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("(anonymous user #{})", self.id),
+        }
+    }
+
+    // every star the member got as (day, part, timestamp), in the order they were earned
+    fn timeline(&self) -> Vec<(u32, usize, u64)> {
+        let mut stars: Vec<_> = self
+            .days
+            .iter()
+            .flat_map(|(&day, parts)| {
+                parts
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(i, star)| star.map(|s| (s, day, i + 1)))
+            })
+            .collect();
+        stars.sort();
+        stars
+            .into_iter()
+            .map(|((ts, _), day, part)| (day, part, ts))
+            .collect()
+    }
+
+    // seconds between the first and the second star of every fully solved day
+    fn deltas(&self) -> Vec<(u32, u64)> {
+        self.days
+            .iter()
+            .filter_map(|(&day, parts)| match parts {
+                // a second star before the first only happens in malformed exports
+                [Some((first, _)), Some((second, _))] => Some((day, second.checked_sub(*first)?)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+// a private leaderboard, from the json export of
+// `https://adventofcode.com/<year>/leaderboard/private/view/<id>.json`
+#[derive(Debug)]
+pub struct Leaderboard {
+    event: u64,
+    owner_id: u64,
+    members: Vec<Member>,
+}
+
+impl FromStr for Leaderboard {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let raw: RawLeaderboard = serde_json::from_str(s)?;
+        Ok(Leaderboard {
+            event: raw.event,
+            owner_id: raw.owner_id,
+            members: raw
+                .members
+                .into_values()
+                .map(Member::try_from)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scoring {
+    // the official rule: the first of n members to get a star scores n, the next n - 1, ...
+    Local,
+    // like local but as if the leaderboard had this many members, nobody scores below zero
+    Pool(u64),
+    // every star is worth the same
+    Flat(u64),
+}
+
+impl FromStr for Scoring {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.split_once('=') {
+            None if s == "local" => Ok(Scoring::Local),
+            Some(("pool", n)) => Ok(Scoring::Pool(n.parse()?)),
+            Some(("flat", n)) => Ok(Scoring::Flat(n.parse()?)),
+            _ => err!("unknown scoring rule {s:?}, expect local, pool=N or flat=N"),
+        }
+    }
+}
+
+// as it is parsed
+impl fmt::Display for Scoring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scoring::Local => write!(f, "local"),
+            Scoring::Pool(n) => write!(f, "pool={n}"),
+            Scoring::Flat(n) => write!(f, "flat={n}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => err!("unknown format {s:?}, expect text or json"),
+        }
+    }
+}
+
+// what the report of a leaderboard shows, the days of the ranking tables are every day with
+// a star when none is given
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub scoring: Scoring,
+    pub skip_days: Vec<u32>,
+    pub days: Vec<u32>,
+    pub format: Format,
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Report {
+            scoring: Scoring::Local,
+            skip_days: vec![],
+            days: vec![],
+            format: Format::Text,
+        }
+    }
+}
+
+// puzzles unlock at midnight EST (UTC-5)
+fn unlock_ts(year: u64, day: u32) -> u64 {
+    // days since the unix epoch of the civil date year-12-01
+    let y = year as i64;
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * 9 + 2) / 5; // march-based day of year of december 1st
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    (days as u64 + day as u64 - 1) * 86400 + 5 * 3600
+}
+
+impl Leaderboard {
+    fn days(&self) -> Vec<u32> {
+        let mut days: Vec<_> = self
+            .members
+            .iter()
+            .flat_map(|m| m.days.keys().copied())
+            .collect();
+        days.sort();
+        days.dedup();
+        days
+    }
+
+    // members ordered by the time they got the given star
+    fn star_order(&self, day: u32, part: usize) -> Vec<usize> {
+        let mut order: Vec<_> = self
+            .members
+            .iter()
+            .enumerate()
+            .filter_map(|(i, m)| m.days.get(&day).and_then(|p| p[part - 1]).map(|s| (s, i)))
+            .collect();
+        order.sort();
+        order.into_iter().map(|(_, i)| i).collect()
+    }
+
+    // score of every member, indexed like `members`
+    fn scores(&self, scoring: Scoring, skip_days: &[u32]) -> Vec<u64> {
+        let n = self.members.len() as u64;
+        let mut scores = vec![0; self.members.len()];
+        for day in self.days() {
+            if skip_days.contains(&day) {
+                continue;
+            }
+            for part in 1..=2 {
+                for (rank, i) in self.star_order(day, part).into_iter().enumerate() {
+                    scores[i] += match scoring {
+                        Scoring::Local => n - rank as u64,
+                        Scoring::Pool(pool) => pool.saturating_sub(rank as u64),
+                        Scoring::Flat(points) => points,
+                    };
+                }
+            }
+        }
+        scores
+    }
+
+    // (member, first star, second star) of one day with times relative to the unlock,
+    // ranked like the official leaderboard: second star first, then first star
+    fn day_ranking(&self, day: u32) -> Vec<(usize, Option<u64>, Option<u64>)> {
+        let unlock = unlock_ts(self.event, day);
+        let mut rows: Vec<_> = self
+            .members
+            .iter()
+            .enumerate()
+            .filter_map(|(i, m)| m.days.get(&day).map(|parts| (i, parts)))
+            .collect();
+        rows.sort_by_key(|(_, [first, second])| (second.is_none(), *second, *first));
+        rows.into_iter()
+            .map(|(i, [first, second])| {
+                let elapsed = |star: &Option<Star>| star.map(|(ts, _)| ts.saturating_sub(unlock));
+                (i, elapsed(first), elapsed(second))
+            })
+            .collect()
+    }
+
+    // member indices ordered by score, ties broken by the last star time
+    fn standings(&self, scores: &[u64]) -> Vec<usize> {
+        let mut order: Vec<_> = (0..self.members.len()).collect();
+        // a member without stars ranks after everyone with the same score
+        let last_star = |i: usize| self.members[i].timeline().last().map_or(u64::MAX, |s| s.2);
+        order.sort_by_key(|&i| {
+            (
+                std::cmp::Reverse(scores[i]),
+                last_star(i),
+                self.members[i].id,
+            )
+        });
+        order
+    }
+}
+
+fn format_duration(secs: u64) -> String {
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn format_optional(secs: Option<u64>) -> String {
+    secs.map(format_duration).unwrap_or_else(|| "-".to_string())
+}
+
+pub fn report(board: &Leaderboard, options: &Report) -> String {
+    let Report {
+        scoring,
+        ref skip_days,
+        ref days,
+        format,
+    } = *options;
+    let all_days = board.days();
+    let days = match days.is_empty() {
+        true => &all_days,
+        false => days,
+    };
+    let scores = board.scores(scoring, skip_days);
+    let standings = board.standings(&scores);
+    let mut out = String::new();
+    match format {
+        Format::Text => {
+            writeln!(out, "Leaderboard {} of {}", board.owner_id, board.event).unwrap();
+            writeln!(out, "Scoring: {scoring}").unwrap();
+            writeln!(
+                out,
+                "{:>4} {:>6} {:>6} {:>5}  name",
+                "rank", "score", "local", "stars"
+            )
+            .unwrap();
+            for (rank, &i) in standings.iter().enumerate() {
+                let m = &board.members[i];
+                writeln!(
+                    out,
+                    "{:>4} {:>6} {:>6} {:>5}  {}",
+                    rank + 1,
+                    scores[i],
+                    m.local_score,
+                    m.stars,
+                    m.display_name()
+                )
+                .unwrap();
+            }
+            for &day in days {
+                writeln!(out, "\nDay {day}").unwrap();
+                writeln!(
+                    out,
+                    "{:>4} {:>10} {:>10} {:>10}  name",
+                    "rank", "part 1", "part 2", "delta"
+                )
+                .unwrap();
+                for (rank, (i, first, second)) in board.day_ranking(day).into_iter().enumerate() {
+                    let delta = first.zip(second).and_then(|(a, b)| b.checked_sub(a));
+                    writeln!(
+                        out,
+                        "{:>4} {:>10} {:>10} {:>10}  {}",
+                        rank + 1,
+                        format_optional(first),
+                        format_optional(second),
+                        format_optional(delta),
+                        board.members[i].display_name()
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        Format::Json => {
+            let members: Vec<_> = standings
+                .iter()
+                .map(|&i| {
+                    let m = &board.members[i];
+                    let timeline: Vec<_> = m
+                        .timeline()
+                        .iter()
+                        .map(|(day, part, ts)| json!({"day": day, "part": part, "ts": ts}))
+                        .collect();
+                    let deltas: Vec<_> = m
+                        .deltas()
+                        .iter()
+                        .map(|(day, secs)| json!({"day": day, "seconds": secs}))
+                        .collect();
+                    json!({
+                        "id": m.id,
+                        "name": m.display_name(),
+                        "score": scores[i],
+                        "local_score": m.local_score,
+                        "stars": m.stars,
+                        "timeline": timeline,
+                        "deltas": deltas,
+                    })
+                })
+                .collect();
+            let days: Vec<_> = days
+                .iter()
+                .map(|&day| {
+                    let rows: Vec<_> = board
+                        .day_ranking(day)
+                        .into_iter()
+                        .map(|(i, first, second)| {
+                            json!({"id": board.members[i].id, "part1": first, "part2": second})
+                        })
+                        .collect();
+                    json!({"day": day, "ranking": rows})
+                })
+                .collect();
+            let value = json!({
+                "event": board.event,
+                "owner_id": board.owner_id,
+                "members": members,
+                "days": days,
+            });
+            write!(out, "{value}").unwrap();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+fn example() -> Leaderboard {
+    include_str!("../fixtures/leaderboard.json")
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn example_parse() {
+    let board = example();
+    assert_eq!(board.event, 2023);
+    assert_eq!(board.owner_id, 101);
+    assert_eq!(board.members.len(), 3);
+    assert_eq!(board.days(), vec![1, 2, 3]);
+    assert_eq!(board.members[2].display_name(), "(anonymous user #303)");
+    assert_eq!(unlock_ts(2023, 1), 1701406800);
+    assert_eq!(unlock_ts(2023, 25), 1703480400);
+
+    let alice = &board.members[0];
+    assert_eq!(
+        alice.timeline(),
+        vec![
+            (1, 1, 1701407100),
+            (1, 2, 1701407400),
+            (2, 1, 1701494000),
+            (2, 2, 1701495000),
+            (3, 1, 1701580000)
+        ]
+    );
+    assert_eq!(alice.deltas(), vec![(1, 300), (2, 1000)]);
+    assert!("{\"members\": {}}".parse::<Leaderboard>().is_err());
+}
+
+#[test]
+fn example_scores() {
+    let board = example();
+    let local: Vec<_> = board.members.iter().map(|m| m.local_score).collect();
+    assert_eq!(board.scores(Scoring::Local, &[]), local);
+    assert_eq!(board.scores(Scoring::Pool(2), &[]), vec![7, 5, 4]);
+    assert_eq!(board.scores(Scoring::Flat(1), &[]), vec![5, 3, 3]);
+    assert_eq!(board.scores(Scoring::Local, &[1]), vec![7, 3, 6]);
+    for scoring in [Scoring::Local, Scoring::Pool(2), Scoring::Flat(3)] {
+        assert_eq!(scoring.to_string().parse::<Scoring>().unwrap(), scoring);
+    }
+    assert_eq!("pool=2".parse::<Scoring>().unwrap(), Scoring::Pool(2));
+    assert!("global".parse::<Scoring>().is_err());
+
+    let scores = board.scores(Scoring::Local, &[1]);
+    assert_eq!(board.standings(&scores), vec![0, 2, 1]);
+}
+
+#[test]
+fn example_report() {
+    let board = example();
+    assert_eq!(
+        board.day_ranking(1),
+        vec![
+            (0, Some(300), Some(600)),
+            (1, Some(200), Some(1200)),
+            (2, Some(93200), None)
+        ]
+    );
+
+    let options = |scoring, days: &[u32], format| Report {
+        scoring,
+        skip_days: vec![],
+        days: days.to_vec(),
+        format,
+    };
+    let text = report(&board, &options(Scoring::Local, &[3], Format::Text));
+    assert!(text.contains("Scoring: local\n"));
+    assert!(text.contains("   1     12     12     5  alice"));
+    assert!(text.contains("   1   00:05:00   00:15:00   00:10:00  (anonymous user #303)"));
+
+    let value: serde_json::Value = serde_json::from_str(&report(
+        &board,
+        &options(Scoring::Local, &[1, 2], Format::Json),
+    ))
+    .unwrap();
+    let Some(serde_json::Value::Array(members)) = value.get("members") else {
+        panic!("members is not an array")
+    };
+    assert_eq!(
+        members[0].get("name").and_then(serde_json::Value::as_str),
+        Some("alice")
+    );
+    assert_eq!(
+        members[0].get("score").and_then(serde_json::Value::as_u64),
+        Some(12)
+    );
+    let Some(serde_json::Value::Array(days)) = value.get("days") else {
+        panic!("days is not an array")
+    };
+    assert_eq!(days.len(), 2);
+    // every day with a star when no day is given
+    let text = report(&board, &Report::default());
+    assert!(text.contains("\nDay 1\n") && text.contains("\nDay 3\n"));
+}
+
+#[test]
+fn malformed_export() {
+    // carol got her second star before the first, dave has no stars at all
+    let board: Leaderboard = r#"{"event": 2023, "members": {
+        "1": {"id": "1", "name": "carol", "completion_day_level": {"1": {
+            "1": {"get_star_ts": 1701407500}, "2": {"get_star_ts": 1701407000}}}},
+        "2": {"id": 2, "name": "dave"}}}"#
+        .parse()
+        .unwrap();
+    assert_eq!(board.members[0].deltas(), vec![]);
+    let options = Report {
+        scoring: Scoring::Flat(0),
+        days: vec![1],
+        ..Report::default()
+    };
+    let text = report(&board, &options);
+    assert!(text.contains("   1   00:11:40   00:03:20          -  carol"));
+    // everyone scores 0, the member without stars comes last
+    assert_eq!(board.standings(&[0, 0]), vec![0, 1]);
+
+    assert!(r#"{"event": "x", "members": {}}"#.parse::<Leaderboard>().is_err());
+    let bad_part = r#"{"event": 2023, "members": {"1": {"id": 1,
+        "completion_day_level": {"1": {"3": {"get_star_ts": 1}}}}}}"#;
+    assert!(bad_part.parse::<Leaderboard>().is_err());
+}
@@ -0,0 +1,385 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+mod fetch;
+mod leaderboard;
+mod registry;
+
+use fetch::{Fetcher, Saved, Web};
+use leaderboard::{Leaderboard, Report};
+use registry::{Day, Registry};
+
+#[allow(unused_macros)]
+macro_rules! err {
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
+}
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+// a private leaderboard by the id of its owner, or its json export saved as a file
+#[derive(Debug, Clone, PartialEq)]
+enum Board {
+    Id(u64),
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Cmd {
+    List,
+    Fetch(u8),
+    Run(u8),
+    Leaderboard(Board),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Options {
+    cmd: Cmd,
+    year: Option<u16>,
+    saved: Option<PathBuf>,
+    report: Report,
+}
+
+impl Options {
+    // `list`, `fetch <day>`, `run <day>` or `leaderboard <id|file>`, `--year <year>` defaults
+    // to the latest registered year, `--saved <dir>` reads inputs from `<dir>/<year>/<day>.txt`
+    // and leaderboards from `<dir>/<year>/leaderboard/<id>.json` instead of the website;
+    // `--scoring local|pool=N|flat=N`, `--skip-day <day>`, `--day <day>` and
+    // `--format text|json` shape the leaderboard report
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut cmd = vec![];
+        let mut year = None;
+        let mut saved = None;
+        let mut report = Report::default();
+        while let Some(arg) = args.next() {
+            if let Some(flag) = ["--scoring", "--skip-day", "--day", "--format"]
+                .into_iter()
+                .find(|&flag| flag == arg)
+            {
+                let Some(value) = args.next() else {
+                    return err!("missing a value of {flag}");
+                };
+                match flag {
+                    "--scoring" => report.scoring = value.parse()?,
+                    "--skip-day" => report.skip_days.push(value.parse()?),
+                    "--day" => report.days.push(value.parse()?),
+                    _ => report.format = value.parse()?,
+                }
+                continue;
+            }
+            match arg.as_str() {
+                "--year" => match args.next() {
+                    Some(y) => year = Some(y.parse()?),
+                    None => return err!("missing the year after --year"),
+                },
+                "--saved" => match args.next() {
+                    Some(dir) => saved = Some(PathBuf::from(dir)),
+                    None => return err!("missing the directory after --saved"),
+                },
+                _ => cmd.push(arg),
+            }
+        }
+        let cmd = match &cmd[..] {
+            [c] if c == "list" => Cmd::List,
+            [c, day] if c == "fetch" => Cmd::Fetch(day.parse()?),
+            [c, day] if c == "run" => Cmd::Run(day.parse()?),
+            [c, board] if c == "leaderboard" => Cmd::Leaderboard(match board.parse() {
+                Ok(id) => Board::Id(id),
+                Err(_) => Board::File(PathBuf::from(board)),
+            }),
+            _ => {
+                return err!(
+                    "unknown command {cmd:?}, expect list, fetch <day>, run <day> or \
+                     leaderboard <id|file> with --year <year> and --saved <dir>"
+                )
+            }
+        };
+        Ok(Options {
+            cmd,
+            year,
+            saved,
+            report,
+        })
+    }
+
+    fn fetcher(&self) -> Result<Box<dyn Fetcher>> {
+        if let Some(dir) = &self.saved {
+            return Ok(Box::new(Saved { dir: dir.clone() }));
+        }
+        match std::env::var("AOC_SESSION") {
+            Ok(session) => Ok(Box::new(Web { session })),
+            Err(_) => err!("set AOC_SESSION to the session cookie or use --saved <dir>"),
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let options = Options::from_args(std::env::args().skip(1))?;
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    let registry: Registry = std::fs::read_to_string(root.join("aoc/days.txt"))?.parse()?;
+    let year = match options.year.or(registry.latest_year()) {
+        Some(year) => year,
+        None => return err!("no day is registered"),
+    };
+
+    match options.cmd {
+        Cmd::List => {
+            for day in registry.year(year) {
+                let input = match day.input(root).exists() {
+                    true => "input",
+                    false => "no input",
+                };
+                let answers = day.answers.iter().filter(|a| a.is_some()).count();
+                writeln!(
+                    io::stdout(),
+                    "{year} day {:>2}: {} ({input}, {answers} answers)",
+                    day.day,
+                    day.dir.display()
+                )?;
+            }
+        }
+        Cmd::Fetch(d) => {
+            let day = registry.get(year, d)?;
+            let path = day.input(root);
+            if fetch(&path, day, options.fetcher()?.as_ref())? {
+                writeln!(io::stdout(), "saved {}", path.display())?;
+            } else {
+                writeln!(io::stdout(), "{} already exists", path.display())?;
+            }
+        }
+        Cmd::Run(d) => {
+            let day = registry.get(year, d)?;
+            let path = day.input(root);
+            if !path.exists() {
+                fetch(&path, day, options.fetcher()?.as_ref())?;
+            }
+            let output = run(root, day, &path)?;
+            write!(io::stdout(), "{output}")?;
+            let mut wrong = 0;
+            for (part, verdict) in check(day, &output).iter().enumerate() {
+                writeln!(io::stdout(), "part {}: {verdict}", part + 1)?;
+                wrong += matches!(verdict, Verdict::Wrong { .. }) as usize;
+            }
+            if wrong > 0 {
+                return err!("{wrong} wrong answers for day {d} of {year}");
+            }
+        }
+        Cmd::Leaderboard(ref board) => {
+            writeln!(io::stdout(), "{}", leaderboard(&options, board, year)?)?;
+        }
+    }
+    Ok(())
+}
+
+// the report of a private leaderboard of the year, fetched like the inputs unless it is a file
+fn leaderboard(options: &Options, board: &Board, year: u16) -> Result<String> {
+    let json = match board {
+        Board::Id(id) => options.fetcher()?.leaderboard(year, *id)?,
+        Board::File(path) => match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) => return err!("unable to read {}: {e}", path.display()),
+        },
+    };
+    let board: Leaderboard = json.parse()?;
+    Ok(leaderboard::report(&board, &options.report))
+}
+
+// saves the input of a day to `path` unless it is already there, whether it was fetched
+fn fetch(path: &Path, day: &Day, fetcher: &dyn Fetcher) -> Result<bool> {
+    if path.exists() {
+        return Ok(false);
+    }
+    let input = fetcher.input(day.year, day.day)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, input)?;
+    Ok(true)
+}
+
+// the stdout of the day's crate in release mode with its input on stdin
+fn run(root: &Path, day: &Day, input: &Path) -> Result<String> {
+    let output = Command::new("cargo")
+        .args(["run", "--release", "--quiet", "--manifest-path"])
+        .arg(root.join(&day.dir).join("Cargo.toml"))
+        .stdin(std::fs::File::open(input)?)
+        .stderr(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return err!(
+            "day {} of {} failed with {}",
+            day.day,
+            day.year,
+            output.status
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+// the first answer printed for every part, from lines like `Part 1: 42` or
+// `Part 2 with graph: 42`
+fn answers(output: &str) -> [Option<String>; 2] {
+    let mut answers = [None, None];
+    for line in output.lines() {
+        let Some((label, answer)) = line.split_once(':') else {
+            continue;
+        };
+        let part = match label.split_whitespace().collect::<Vec<_>>()[..] {
+            ["Part", "1", ..] => 0,
+            ["Part", "2", ..] => 1,
+            _ => continue,
+        };
+        answers[part].get_or_insert_with(|| answer.trim().to_string());
+    }
+    answers
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Verdict {
+    Correct(String),
+    Wrong { found: String, expected: String },
+    // no answer is registered for the part yet
+    Unknown(String),
+    Missing,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Correct(a) => write!(f, "{a} is correct"),
+            Verdict::Wrong { found, expected } => write!(f, "{found} is wrong, expect {expected}"),
+            Verdict::Unknown(a) => write!(f, "{a} is not checked, no answer is registered"),
+            Verdict::Missing => write!(f, "no answer printed"),
+        }
+    }
+}
+
+fn check(day: &Day, output: &str) -> [Verdict; 2] {
+    let found = answers(output);
+    [0, 1].map(|part| match (&found[part], &day.answers[part]) {
+        (None, _) => Verdict::Missing,
+        (Some(f), None) => Verdict::Unknown(f.clone()),
+        (Some(f), Some(e)) if f == e => Verdict::Correct(f.clone()),
+        (Some(f), Some(e)) => Verdict::Wrong {
+            found: f.clone(),
+            expected: e.clone(),
+        },
+    })
+}
+
+#[test]
+fn options() {
+    let parse = |s: &str| Options::from_args(s.split_whitespace().map(str::to_string));
+    assert_eq!(
+        parse("run 5 --year 2022").unwrap(),
+        Options {
+            cmd: Cmd::Run(5),
+            year: Some(2022),
+            saved: None,
+            report: Report::default()
+        }
+    );
+    let options = parse("leaderboard 101 --scoring pool=10 --day 3 --format json").unwrap();
+    assert_eq!(options.cmd, Cmd::Leaderboard(Board::Id(101)));
+    assert_eq!(options.report.scoring, leaderboard::Scoring::Pool(10));
+    assert_eq!(options.report.days, [3]);
+    assert_eq!(options.report.format, leaderboard::Format::Json);
+    assert_eq!(
+        parse("leaderboard board.json").unwrap().cmd,
+        Cmd::Leaderboard(Board::File(PathBuf::from("board.json")))
+    );
+    assert_eq!(parse("list").unwrap().cmd, Cmd::List);
+    assert_eq!(
+        parse("--saved inputs fetch 1").unwrap().saved,
+        Some(PathBuf::from("inputs"))
+    );
+    for bad in [
+        "",
+        "run",
+        "run x",
+        "fetch 1 2",
+        "list --year",
+        "leaderboard",
+        "leaderboard 1 --scoring global",
+        "leaderboard 1 --format",
+    ] {
+        assert!(parse(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn check_answers() {
+    let registry: Registry = "2023 13 aoc13 405 400\n2023 20 aoc20 32000000"
+        .parse()
+        .unwrap();
+    let output = "Part 1:405\n> Time elapsed is: 1ms\nPart 2 with grid: 401\nPart 2: 400\n";
+    assert_eq!(answers(output), [Some("405".into()), Some("401".into())]);
+    assert_eq!(
+        check(registry.get(2023, 13).unwrap(), output),
+        [
+            Verdict::Correct("405".into()),
+            Verdict::Wrong {
+                found: "401".into(),
+                expected: "400".into()
+            }
+        ]
+    );
+    let output = "Part 1: 32000000\nPart 2: Manual Calculation Part Two\n";
+    assert_eq!(
+        check(registry.get(2023, 20).unwrap(), output),
+        [
+            Verdict::Correct("32000000".into()),
+            Verdict::Unknown("Manual Calculation Part Two".into())
+        ]
+    );
+    assert_eq!(
+        check(registry.get(2023, 20).unwrap(), "")[0],
+        Verdict::Missing
+    );
+}
+
+#[test]
+fn fetch_saved_inputs() {
+    let dir = std::env::temp_dir().join(format!("aoc-fetch-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("saved/2023")).unwrap();
+    std::fs::write(dir.join("saved/2023/1.txt"), "1abc2\n").unwrap();
+    let registry: Registry = "2023 1 aoc01\n2023 2 aoc02".parse().unwrap();
+    let fetcher = Saved {
+        dir: dir.join("saved"),
+    };
+
+    let day = registry.get(2023, 1).unwrap();
+    let path = day.input(&dir);
+    assert!(fetch(&path, day, &fetcher).unwrap());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "1abc2\n");
+    // an input that is already there is kept
+    assert!(!fetch(&path, day, &fetcher).unwrap());
+
+    let day = registry.get(2023, 2).unwrap();
+    assert!(fetch(&day.input(&dir), day, &fetcher).is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fetch_saved_leaderboard() {
+    let dir = std::env::temp_dir().join(format!("aoc-leaderboard-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("2023/leaderboard")).unwrap();
+    let json = include_str!("../fixtures/leaderboard.json");
+    std::fs::write(dir.join("2023/leaderboard/101.json"), json).unwrap();
+    let options = Options {
+        cmd: Cmd::Leaderboard(Board::Id(101)),
+        year: None,
+        saved: Some(dir.clone()),
+        report: Report::default(),
+    };
+
+    let report = leaderboard(&options, &Board::Id(101), 2023).unwrap();
+    assert!(report.starts_with("Leaderboard 101 of 2023\nScoring: local\n"));
+    // the same export as a file
+    let file = Board::File(dir.join("2023/leaderboard/101.json"));
+    assert_eq!(leaderboard(&options, &file, 2023).unwrap(), report);
+    assert!(leaderboard(&options, &Board::Id(102), 2023).is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+macro_rules! err {
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
+}
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+pub type Key = (u16, u8);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Day {
+    pub year: u16,
+    pub day: u8,
+    // the crate directory relative to the repository root
+    pub dir: PathBuf,
+    // the accepted answer of every part, None when it is not known yet
+    pub answers: [Option<String>; 2],
+}
+
+impl Day {
+    pub fn input(&self, root: &Path) -> PathBuf {
+        root.join(&self.dir).join("input/input.txt")
+    }
+}
+
+// every day of every year, see `days.txt`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Registry {
+    days: BTreeMap<Key, Day>,
+}
+
+impl Registry {
+    pub fn get(&self, year: u16, day: u8) -> Result<&Day> {
+        match self.days.get(&(year, day)) {
+            Some(day) => Ok(day),
+            None => err!("day {day} of {year} is not registered"),
+        }
+    }
+
+    pub fn latest_year(&self) -> Option<u16> {
+        self.days.keys().map(|&(year, _)| year).max()
+    }
+
+    pub fn year(&self, year: u16) -> impl Iterator<Item = &Day> {
+        self.days
+            .range((year, 0)..=(year, u8::MAX))
+            .map(|(_, day)| day)
+    }
+}
+
+impl FromStr for Registry {
+    type Err = Box<dyn Error>;
+
+    // one `year day crate part1 part2` per line, `-` for an unknown answer, blank lines and
+    // lines starting with `#` are skipped
+    fn from_str(s: &str) -> Result<Self> {
+        let mut days = BTreeMap::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<_> = line.split_whitespace().collect();
+            let [year, day, dir, rest @ ..] = &fields[..] else {
+                return err!("unable to parse line {}: {line:?}", i + 1);
+            };
+            if rest.len() > 2 {
+                return err!("line {}: more than two answers in {line:?}", i + 1);
+            }
+            let (year, day) = (year.parse()?, day.parse()?);
+            if !(1..=25).contains(&day) {
+                return err!("line {}: day {day} is not within 1..=25", i + 1);
+            }
+            let answer = |part: usize| rest.get(part).filter(|&&a| a != "-").map(|a| a.to_string());
+            let entry = Day {
+                year,
+                day,
+                dir: PathBuf::from(dir),
+                answers: [answer(0), answer(1)],
+            };
+            if days.insert((year, day), entry).is_some() {
+                return err!("line {}: day {day} of {year} is registered twice", i + 1);
+            }
+        }
+        Ok(Registry { days })
+    }
+}
+
+#[test]
+fn parse_registry() {
+    let registry: Registry =
+        "# comment\n2023 1 aoc01 142 281\n\n2023 25 aoc25 54\n2022 1 2022/aoc01"
+            .parse()
+            .unwrap();
+    assert_eq!(registry.latest_year(), Some(2023));
+    let days: Vec<_> = registry.year(2023).map(|d| d.day).collect();
+    assert_eq!(days, [1, 25]);
+    let day = registry.get(2023, 25).unwrap();
+    assert_eq!(day.answers, [Some("54".to_string()), None]);
+    assert_eq!(
+        day.input(Path::new("/repo")),
+        Path::new("/repo/aoc25/input/input.txt")
+    );
+    assert_eq!(registry.get(2022, 1).unwrap().answers, [None, None]);
+    assert!(registry.get(2022, 2).is_err());
+
+    for bad in [
+        "2023 1",
+        "2023 26 aoc26",
+        "2023 1 a 1 2 3",
+        "2023 1 a\n2023 1 b",
+    ] {
+        assert!(bad.parse::<Registry>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn registered_days() {
+    let registry: Registry = include_str!("../days.txt").parse().unwrap();
+    assert_eq!(registry.latest_year(), Some(2023));
+    assert_eq!(registry.year(2023).count(), 25);
+    for day in registry.year(2023) {
+        assert_eq!(day.dir, Path::new(&format!("aoc{:02}", day.day)));
+    }
+}
@@ -0,0 +1,157 @@
+use std::error::Error;
+use std::fmt;
+use std::io::BufRead;
+
+use crate::vocab::{Calibration, Matcher};
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    // every line without a digit is reported
+    Strict,
+    // lines without a digit are skipped
+    Lenient,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BadLine {
+    pub line: usize,
+    pub text: String,
+}
+
+impl fmt::Display for BadLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: no digit in {:?}", self.line, self.text)
+    }
+}
+
+impl Error for BadLine {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BadLines(pub Vec<BadLine>);
+
+impl fmt::Display for BadLines {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} lines without a digit", self.0.len())?;
+        for bad in &self.0 {
+            write!(f, "\n  {bad}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for BadLines {}
+
+// the sum of a matcher does not fit in a u64
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overflow {
+    pub line: usize,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: the calibration sum overflows", self.line)
+    }
+}
+
+impl Error for Overflow {}
+
+// the sum of the calibration values of every matcher in a single pass over the reader,
+// `inspect` sees the index of the matcher with every line that is counted and every line
+// that is not; an io error ends the document
+pub fn totals<const N: usize>(
+    reader: impl BufRead,
+    matchers: [&Matcher; N],
+    mode: Mode,
+    mut inspect: impl FnMut(usize, std::result::Result<(usize, &Calibration), &BadLine>),
+) -> Result<[u64; N]> {
+    let mut sums = [0u64; N];
+    let mut bad_lines: [Vec<BadLine>; N] = std::array::from_fn(|_| vec![]);
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        for (m, matcher) in matchers.iter().enumerate() {
+            match matcher.calibration(&line) {
+                Some(c) => {
+                    inspect(m, Ok((i + 1, &c)));
+                    sums[m] = sums[m]
+                        .checked_add(c.value as u64)
+                        .ok_or(Overflow { line: i + 1 })?;
+                }
+                None => {
+                    let bad = BadLine {
+                        line: i + 1,
+                        text: line.clone(),
+                    };
+                    inspect(m, Err(&bad));
+                    if mode == Mode::Strict {
+                        bad_lines[m].push(bad);
+                    }
+                }
+            }
+        }
+    }
+    // the lines of the first matcher that has any
+    match bad_lines.into_iter().find(|b| !b.is_empty()) {
+        Some(bad) => Err(BadLines(bad).into()),
+        None => Ok(sums),
+    }
+}
+
+#[test]
+fn strict_and_lenient() {
+    use crate::vocab::Vocabulary;
+
+    let matcher = Matcher::new(&Vocabulary::english());
+    let input = "two1nine\nnothing\nzoneight234\n\n";
+
+    let mut spans = vec![];
+    let sum = totals(input.as_bytes(), [&matcher], Mode::Lenient, |_, line| {
+        if let Ok((line, c)) = line {
+            spans.push((line, c.first.start..c.first.end, c.last.start..c.last.end));
+        }
+    });
+    assert_eq!(sum.unwrap(), [29 + 14]);
+    assert_eq!(spans, [(1, 0..3, 4..8), (3, 1..4, 10..11)]);
+
+    // both matchers see every line of one pass
+    let digits = Matcher::new(&Vocabulary::default());
+    let mut seen = vec![];
+    let sums = totals(
+        input.as_bytes(),
+        [&digits, &matcher],
+        Mode::Lenient,
+        |m, line| seen.push((m, line.map_or_else(|bad| bad.line, |(line, _)| line))),
+    );
+    assert_eq!(sums.unwrap(), [11 + 24, 29 + 14]);
+    assert_eq!(seen.len(), 8);
+    assert_eq!(seen[..4], [(0, 1), (1, 1), (0, 2), (1, 2)]);
+    let err = totals(
+        input.as_bytes(),
+        [&matcher, &digits],
+        Mode::Strict,
+        |_, _| {},
+    );
+    assert_eq!(
+        err.unwrap_err().downcast_ref::<BadLines>().unwrap().0.len(),
+        2
+    );
+    let err = totals(input.as_bytes(), [&matcher], Mode::Strict, |_, _| {}).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<BadLines>().unwrap().0,
+        [
+            BadLine {
+                line: 2,
+                text: "nothing".to_string()
+            },
+            BadLine {
+                line: 4,
+                text: String::new()
+            },
+        ]
+    );
+    assert_eq!(
+        err.to_string(),
+        "2 lines without a digit\n  line 2: no digit in \"nothing\"\n  line 4: no digit in \"\""
+    );
+}
@@ -1,7 +1,19 @@
 use std::error::Error;
-use std::io::{self, Read, Write};
+use std::io::{self, BufRead, Write};
 use std::time::Instant;
 
+mod calibration;
+#[path = "../../common/explain.rs"]
+mod explain;
+mod vocab;
+#[cfg(test)]
+#[path = "../../common/xorshift.rs"]
+mod xorshift;
+
+use calibration::{totals, BadLine, Mode};
+use explain::{Explain, Trace};
+use vocab::{Calibration, Matcher, Vocabulary};
+
 #[allow(unused_macros)]
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
@@ -9,116 +21,111 @@ macro_rules! err {
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
-fn main() -> Result<()> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-
-    part1(&input)?;
-    part2(&input)?;
-    Ok(())
+#[derive(Debug, Default)]
+struct Options {
+    explain: Option<Explain>,
+    vocab: Option<String>,
+    lenient: bool,
 }
 
-fn match_digit_with_letters(chars: &[char]) -> Option<(u32, usize)> {
-    if chars.is_empty() {
-        return None;
-    }
-    let l = chars.len();
-    match chars[0] {
-        'o' => {
-            if 3 <= l && chars[..3] == ['o', 'n', 'e'] {
-                return Some((1, 1));
-            }
-        }
-        't' => {
-            if 3 <= l && chars[..3] == ['t', 'w', 'o'] {
-                return Some((2, 2));
-            }
-            if 5 <= l && chars[..5] == ['t', 'h', 'r', 'e', 'e'] {
-                return Some((3, 3));
-            }
-        }
-        'f' => {
-            if 4 <= l && chars[..4] == ['f', 'o', 'u', 'r'] {
-                return Some((4, 1));
-            }
-            if 4 <= l && chars[..4] == ['f', 'i', 'v', 'e'] {
-                return Some((5, 3));
-            }
-        }
-        's' => {
-            if 3 <= l && chars[..3] == ['s', 'i', 'x'] {
-                return Some((6, 3));
-            }
-            if 5 <= l && chars[..5] == ['s', 'e', 'v', 'e', 'n'] {
-                return Some((7, 1));
-            }
-        }
-        'e' => {
-            if 5 <= l && chars[..5] == ['e', 'i', 'g', 'h', 't'] {
-                return Some((8, 4));
-            }
-        }
-        'n' => {
-            if 4 <= l && chars[..4] == ['n', 'i', 'n', 'e'] {
-                return Some((9, 2));
-            }
-        }
-        c => {
-            if let Some(n) = c.to_digit(10) {
-                return Some((n, 1));
+impl Options {
+    // `--explain` prints the trace as text, `--explain=json` as json,
+    // `--vocab <file>` reads the number words of part 2 from a file,
+    // `--lenient` skips the lines without a digit instead of reporting them
+    fn from_args() -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--vocab" => match args.next() {
+                    Some(path) => options.vocab = Some(path),
+                    None => return err!("missing the vocabulary file after --vocab"),
+                },
+                "--lenient" => options.lenient = true,
+                _ => match Explain::from_arg(&arg) {
+                    Some(explain) => options.explain = Some(explain),
+                    None => {
+                        return err!(
+                            "unknown argument {arg:?}, expect --explain[=text|json], --vocab <file> or --lenient"
+                    )
+                    }
+                },
             }
         }
+        Ok(options)
     }
-    None
 }
 
-fn part1(input: &str) -> Result<u32> {
-    let start = Instant::now();
+fn main() -> Result<()> {
+    let options = Options::from_args()?;
+    let mut trace = Trace::new(options.explain.is_some());
+    let vocab = match &options.vocab {
+        Some(path) => std::fs::read_to_string(path)?.parse()?,
+        None => Vocabulary::english(),
+    };
 
-    let mut sum = 0;
-    for line in input.lines() {
-        let first_digit = match line.chars().find_map(|c| c.to_digit(10)) {
-            None => return err!("unable to find the first digit for the calibration value"),
-            Some(c) => c,
-        };
-        let last_digit = match line.chars().rev().find_map(|c| c.to_digit(10)) {
-            None => return err!("unable to find the first digit for the calibration value"),
-            Some(c) => c,
-        };
-        sum += first_digit * 10 + last_digit;
+    let mode = if options.lenient {
+        Mode::Lenient
+    } else {
+        Mode::Strict
+    };
+    parts(io::stdin().lock(), &vocab, mode, &mut trace)?;
+    if let Some(explain) = options.explain {
+        writeln!(io::stdout(), "{}", trace.report(explain))?;
     }
+    Ok(())
+}
 
-    writeln!(io::stdout(), "Part 1: {}", sum)?;
-    writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
-    Ok(sum)
+// records the digits of a counted line under `step`, and a line without a digit as reported
+// or skipped by `mode`
+fn record(
+    step: &'static str,
+    line: std::result::Result<(usize, &Calibration), &BadLine>,
+    mode: Mode,
+    trace: &mut Trace,
+) {
+    match line {
+        Ok((l, c)) => trace.record(step, || {
+            vec![
+                ("line", l.to_string()),
+                ("first", c.first.first_digit().to_string()),
+                ("last", c.last.last_digit().to_string()),
+            ]
+        }),
+        Err(bad) => {
+            let outcome = match mode {
+                Mode::Strict => "reported",
+                Mode::Lenient => "skipped",
+            };
+            trace.record(step, || {
+                vec![
+                    ("line", bad.line.to_string()),
+                    (outcome, "true".to_string()),
+                ]
+            })
+        }
+    }
 }
 
-fn part2(input: &str) -> Result<u32> {
+// both parts in a single pass, so the input is never held in memory as a whole
+fn parts(
+    input: impl BufRead,
+    vocab: &Vocabulary,
+    mode: Mode,
+    trace: &mut Trace,
+) -> Result<(u64, u64)> {
     let start = Instant::now();
 
-    let mut sum = 0;
-    for line in input.lines() {
-        let mut first_digit = 0;
-        let mut last_digit = 0;
-        let chars: Vec<char> = line.chars().collect();
-        let mut i = 0;
-        while i <= chars.len() {
-            if let Some((digit, offset)) = match_digit_with_letters(&chars[i..]) {
-                if first_digit == 0 {
-                    first_digit = digit
-                }
-                last_digit = digit;
-                i += offset;
-            } else {
-                i += 1;
-            }
-        }
+    let digits = Matcher::new(&Vocabulary::default());
+    let words = Matcher::new(vocab);
+    let [sum1, sum2] = totals(input, [&digits, &words], mode, |m, line| {
+        record(["part1.line", "part2.line"][m], line, mode, trace)
+    })?;
 
-        sum += first_digit * 10 + last_digit;
-    }
-    writeln!(io::stdout(), "Part 2: {}", sum)?;
+    writeln!(io::stdout(), "Part 1: {}", sum1)?;
+    writeln!(io::stdout(), "Part 2: {}", sum2)?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
-    Ok(sum)
+    Ok((sum1, sum2))
 }
 
 #[test]
@@ -127,8 +134,18 @@ fn example_input() {
 pqr3stu8vwx
 a1b2c3d4e5f
 treb7uchet";
-    assert_eq!(part1(input).unwrap(), 142);
+    assert_eq!(
+        parts(
+            input.as_bytes(),
+            &Vocabulary::english(),
+            Mode::Strict,
+            &mut Trace::default()
+        )
+        .unwrap(),
+        (142, 142)
+    );
 
+    // some lines have no digit for part 1
     let input = "two1nine
 eightwothree
 abcone2threexyz
@@ -136,13 +153,72 @@ xtwone3four
 4nineeightseven2
 zoneight234
 7pqrstsixteen";
-    assert_eq!(part2(input).unwrap(), 281);
+    assert_eq!(
+        parts(
+            input.as_bytes(),
+            &Vocabulary::english(),
+            Mode::Lenient,
+            &mut Trace::default()
+        )
+        .unwrap()
+        .1,
+        281
+    );
 }
 
 #[test]
 fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
 
-    assert_eq!(part1(&input).unwrap(), 54390);
-    assert_eq!(part2(&input).unwrap(), 54277);
+    assert_eq!(
+        parts(
+            input.as_bytes(),
+            &Vocabulary::english(),
+            Mode::Strict,
+            &mut Trace::default()
+        )
+        .unwrap(),
+        (54390, 54277)
+    );
+}
+
+#[test]
+fn explain_trace() {
+    let english = Vocabulary::english();
+    let mut trace = Trace::new(true);
+    let input = "two1nine\nzoneight234";
+    parts(input.as_bytes(), &english, Mode::Strict, &mut trace).unwrap();
+    assert_eq!(
+        trace.report(Explain::Text),
+        "part1.line line=1 first=1 last=1\npart2.line line=1 first=2 last=9\n\
+         part1.line line=2 first=2 last=4\npart2.line line=2 first=1 last=4"
+    );
+    assert_eq!(
+        trace.report(Explain::Json),
+        r#"[{"step":"part1.line","line":1,"first":1,"last":1},{"step":"part2.line","line":1,"first":2,"last":9},{"step":"part1.line","line":2,"first":2,"last":4},{"step":"part2.line","line":2,"first":1,"last":4}]"#
+    );
+
+    let mut trace = Trace::default();
+    parts("1abc2".as_bytes(), &english, Mode::Strict, &mut trace).unwrap();
+    assert_eq!(trace.report(Explain::Text), "");
+
+    let mut trace = Trace::new(true);
+    let input = "1abc2\nnothing";
+    assert_eq!(
+        parts(input.as_bytes(), &english, Mode::Lenient, &mut trace).unwrap(),
+        (12, 12)
+    );
+    assert_eq!(
+        trace.report(Explain::Text),
+        "part1.line line=1 first=1 last=2\npart2.line line=1 first=1 last=2\n\
+         part1.line line=2 skipped=true\npart2.line line=2 skipped=true"
+    );
+    // a strict run reports the line rather than skipping it
+    let mut trace = Trace::new(true);
+    assert!(parts(input.as_bytes(), &english, Mode::Strict, &mut trace).is_err());
+    assert_eq!(
+        trace.report(Explain::Text),
+        "part1.line line=1 first=1 last=2\npart2.line line=1 first=1 last=2\n\
+         part1.line line=2 reported=true\npart2.line line=2 reported=true"
+    );
 }
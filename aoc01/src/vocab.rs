@@ -0,0 +1,296 @@
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::str::FromStr;
+
+macro_rules! err {
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
+}
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+const ENGLISH: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
+// number words and their values, a value may have several digits, e.g. `twelve 12`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Vocabulary {
+    words: Vec<(String, u32)>,
+}
+
+impl Vocabulary {
+    pub fn english() -> Self {
+        Vocabulary {
+            words: (1..)
+                .zip(ENGLISH)
+                .map(|(v, w)| (w.to_string(), v))
+                .collect(),
+        }
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.words.iter().map(|(w, v)| (w.as_str(), *v))
+    }
+}
+
+impl FromStr for Vocabulary {
+    type Err = Box<dyn Error>;
+
+    // one `word value` per line, blank lines and lines starting with `#` are skipped
+    fn from_str(s: &str) -> Result<Self> {
+        let mut words: Vec<(String, u32)> = vec![];
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((word, value)) = line.split_once(char::is_whitespace) else {
+                return err!(
+                    "unable to parse line {}: {line:?}, expect `word value`",
+                    i + 1
+                );
+            };
+            let value = value.trim().parse()?;
+            match words.iter().find(|(w, _)| w == word) {
+                Some((_, v)) if *v != value => {
+                    return err!("word {word:?} on line {} is both {v} and {value}", i + 1)
+                }
+                Some(_) => {}
+                None => words.push((word.to_string(), value)),
+            }
+        }
+        Ok(Vocabulary { words })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Match {
+    // byte range of the token in the line
+    pub start: usize,
+    pub end: usize,
+    pub value: u32,
+}
+
+impl Match {
+    pub fn first_digit(&self) -> u32 {
+        let mut v = self.value;
+        while v >= 10 {
+            v /= 10;
+        }
+        v
+    }
+
+    pub fn last_digit(&self) -> u32 {
+        self.value % 10
+    }
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    next: HashMap<u8, usize>,
+    fail: usize,
+    // (length, value) of the word ending here
+    word: Option<(usize, u32)>,
+    // the nearest node on the fail chain that ends a word
+    dict: Option<usize>,
+}
+
+// an Aho–Corasick automaton over the bytes of the digits and the words of a vocabulary
+// see: https://en.wikipedia.org/wiki/Aho%E2%80%93Corasick_algorithm
+#[derive(Debug)]
+pub struct Matcher {
+    nodes: Vec<Node>,
+}
+
+impl Matcher {
+    pub fn new(vocab: &Vocabulary) -> Self {
+        let digits: Vec<_> = (0..10).map(|d| (d.to_string(), d)).collect();
+        let mut nodes = vec![Node::default()];
+        for (word, value) in digits
+            .iter()
+            .map(|(w, v)| (w.as_str(), *v))
+            .chain(vocab.words())
+        {
+            let mut curr = 0;
+            for &b in word.as_bytes() {
+                curr = match nodes[curr].next.get(&b) {
+                    Some(&n) => n,
+                    None => {
+                        nodes.push(Node::default());
+                        let n = nodes.len() - 1;
+                        nodes[curr].next.insert(b, n);
+                        n
+                    }
+                };
+            }
+            nodes[curr].word = Some((word.len(), value));
+        }
+
+        let mut queue: VecDeque<_> = nodes[0].next.values().copied().collect();
+        while let Some(curr) = queue.pop_front() {
+            let edges: Vec<_> = nodes[curr].next.iter().map(|(&b, &n)| (b, n)).collect();
+            for (b, n) in edges {
+                let mut fail = nodes[curr].fail;
+                while fail != 0 && !nodes[fail].next.contains_key(&b) {
+                    fail = nodes[fail].fail;
+                }
+                let fail = nodes[fail].next.get(&b).copied().unwrap_or(0);
+                nodes[n].fail = fail;
+                nodes[n].dict = if nodes[fail].word.is_some() {
+                    Some(fail)
+                } else {
+                    nodes[fail].dict
+                };
+                queue.push_back(n);
+            }
+        }
+        Matcher { nodes }
+    }
+
+    // every occurrence of a digit or a word, overlapping ones included, ordered by their ends
+    pub fn find_all(&self, line: &str) -> Vec<Match> {
+        let mut matches = vec![];
+        let mut curr = 0;
+        for (i, b) in line.bytes().enumerate() {
+            while curr != 0 && !self.nodes[curr].next.contains_key(&b) {
+                curr = self.nodes[curr].fail;
+            }
+            curr = self.nodes[curr].next.get(&b).copied().unwrap_or(0);
+
+            let mut out = if self.nodes[curr].word.is_some() {
+                Some(curr)
+            } else {
+                self.nodes[curr].dict
+            };
+            while let Some(n) = out {
+                let (len, value) = self.nodes[n].word.unwrap();
+                matches.push(Match {
+                    start: i + 1 - len,
+                    end: i + 1,
+                    value,
+                });
+                out = self.nodes[n].dict;
+            }
+        }
+        matches
+    }
+
+    // the tokens read from the left and from the right, the longest one wins a tie
+    pub fn calibration(&self, line: &str) -> Option<Calibration> {
+        let matches = self.find_all(line);
+        let first = *matches
+            .iter()
+            .min_by_key(|m| (m.start, usize::MAX - m.end))?;
+        let last = *matches
+            .iter()
+            .max_by_key(|m| (m.end, usize::MAX - m.start))?;
+        Some(Calibration {
+            value: first.first_digit() * 10 + last.last_digit(),
+            first,
+            last,
+        })
+    }
+}
+
+// the calibration value of a line and the tokens its two digits are read from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    pub value: u32,
+    pub first: Match,
+    pub last: Match,
+}
+
+#[test]
+fn overlapping_words() {
+    let matcher = Matcher::new(&Vocabulary::english());
+    let values: Vec<_> = matcher
+        .find_all("xtwone3eightwo")
+        .iter()
+        .map(|m| m.value)
+        .collect();
+    assert_eq!(values, [2, 1, 3, 8, 2]);
+    assert_eq!(
+        matcher.calibration("eightwothree").map(|c| c.value),
+        Some(83)
+    );
+    assert_eq!(matcher.calibration("oneight").map(|c| c.value), Some(18));
+    assert_eq!(matcher.calibration("abc").map(|c| c.value), None);
+
+    let vocab: Vocabulary = "# german\neins 1\nzwei 2\ndrei 3\nfünf 5\nzwölf 12\nelf 11"
+        .parse()
+        .unwrap();
+    let matcher = Matcher::new(&vocab);
+    assert_eq!(matcher.calibration("xzweinsx").map(|c| c.value), Some(21));
+    assert_eq!(matcher.calibration("zwölf").map(|c| c.value), Some(12));
+    assert_eq!(
+        matcher.calibration("fünfzwölfelf").map(|c| c.value),
+        Some(51)
+    );
+    assert!("eins 1\neins 2".parse::<Vocabulary>().is_err());
+    assert!("eins".parse::<Vocabulary>().is_err());
+}
+
+#[test]
+fn matches_naive_search() {
+    fn naive(vocab: &Vocabulary, line: &str) -> Option<u32> {
+        let value = |i: usize, j: usize| {
+            let token = line.get(i..j)?;
+            token
+                .parse::<u32>()
+                .ok()
+                .filter(|_| token.len() == 1)
+                .or_else(|| vocab.words().find(|(w, _)| *w == token).map(|(_, v)| v))
+        };
+        let n = line.len();
+        // earliest start, then longest
+        let first = (0..n).find_map(|i| (i + 1..=n).rev().find_map(|j| value(i, j)))?;
+        // latest end, then longest
+        let last = (1..=n)
+            .rev()
+            .find_map(|j| (0..j).find_map(|i| value(i, j)))?;
+        let mut first_digit = first;
+        while first_digit >= 10 {
+            first_digit /= 10;
+        }
+        Some(first_digit * 10 + last % 10)
+    }
+
+    let mut rng = crate::xorshift::XorShift::new(0x2545_f491_4f6c_dd1d);
+    let mut rand = |n: usize| rng.below(n as u64) as usize;
+
+    let mut english = Vocabulary::english();
+    english.words.extend([
+        ("ten".to_string(), 10),
+        ("eleven".to_string(), 11),
+        ("twelve".to_string(), 12),
+        ("nineteen".to_string(), 19),
+    ]);
+    let german: Vocabulary =
+        "eins 1\nzwei 2\ndrei 3\nvier 4\nfünf 5\nsechs 6\nsieben 7\nacht 8\nneun 9\nzwölf 12"
+            .parse()
+            .unwrap();
+    for vocab in [english, german] {
+        let matcher = Matcher::new(&vocab);
+        let words: Vec<_> = vocab.words().map(|(w, _)| w).collect();
+        for _ in 0..2000 {
+            // glue word fragments, digits and noise so that words often overlap
+            let mut line = String::new();
+            for _ in 0..rand(8) {
+                match rand(4) {
+                    0 => line.push(char::from(b'0' + rand(10) as u8)),
+                    1 => line.push(char::from(b'a' + rand(26) as u8)),
+                    _ => {
+                        let w = words[rand(words.len())];
+                        let cut: Vec<_> = w.char_indices().map(|(i, _)| i).collect();
+                        line += &w[cut[rand(cut.len())]..];
+                    }
+                }
+            }
+            assert_eq!(
+                matcher.calibration(&line).map(|c| c.value),
+                naive(&vocab, &line),
+                "{line:?}"
+            );
+        }
+    }
+}
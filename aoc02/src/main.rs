@@ -1,8 +1,17 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
 use std::io::{self, Read, Write};
 use std::str::FromStr;
 use std::time::Instant;
 
+#[path = "../../common/explain.rs"]
+mod explain;
+#[cfg(test)]
+#[path = "../../common/xorshift.rs"]
+mod xorshift;
+
+use explain::{Explain, Trace};
+
 #[allow(unused_macros)]
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
@@ -10,17 +19,69 @@ macro_rules! err {
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
-#[derive(Debug)]
-enum Cube {
-    Red(usize),
-    Green(usize),
-    Blue(usize),
+#[derive(Debug, Default)]
+struct Options {
+    explain: Option<Explain>,
+    queries: Vec<Query>,
 }
 
-#[derive(Debug)]
+impl Options {
+    // `--explain` prints the trace as text, `--explain=json` as json,
+    // `--possible <bag>`, `--smallest-bag <k>`, `--power` and `--normalise` answer a query
+    // instead of the parts
+    fn from_args() -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing the value of {arg}"));
+            match arg.as_str() {
+                "--possible" => options.queries.push(Query::Possible(parse_bag(&value()?)?)),
+                "--smallest-bag" => options.queries.push(Query::SmallestBag(value()?.parse()?)),
+                "--power" => options.queries.push(Query::Power),
+                "--normalise" => options.queries.push(Query::Normalise),
+                _ => match Explain::from_arg(&arg) {
+                    Some(explain) => options.explain = Some(explain),
+                    None => {
+                        return err!(
+                            "unknown argument {arg:?}, expect --explain[=text|json], \
+                             --possible <bag>, --smallest-bag <k>, --power or --normalise"
+                        )
+                    }
+                },
+            }
+        }
+        Ok(options)
+    }
+}
+
+// a colour to count map
+type Bag = BTreeMap<String, usize>;
+
+// `12 red, 13 green, 14 blue`
+fn parse_bag(s: &str) -> Result<Bag> {
+    let mut bag = Bag::new();
+    for cube in s.split(',').filter(|c| !c.trim().is_empty()) {
+        if let Some((n, c)) = cube.trim().split_once(' ') {
+            *bag.entry(c.trim().to_string()).or_default() += n.trim().parse::<usize>()?;
+        } else {
+            return err!("Unable to parse bag: {:?}", s);
+        }
+    }
+    Ok(bag)
+}
+
+fn display_bag(bag: &Bag) -> String {
+    bag.iter()
+        .map(|(c, n)| format!("{n} {c}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Debug, Clone, PartialEq)]
 struct Game {
     id: usize,
-    sets: Vec<Vec<Cube>>,
+    // the (count, colour) of the cubes of every set, as recorded
+    sets: Vec<Vec<(usize, String)>>,
 }
 
 impl FromStr for Game {
@@ -28,7 +89,7 @@ impl FromStr for Game {
 
     fn from_str(s: &str) -> Result<Self> {
         let id;
-        let mut sets: Vec<Vec<Cube>> = vec![];
+        let mut sets = vec![];
         if let Some((raw_id, record)) = s.trim().split_once(':') {
             if let Some((_, str_id)) = raw_id.trim().split_once(' ') {
                 id = str_id.trim().parse::<usize>()?;
@@ -38,15 +99,16 @@ impl FromStr for Game {
             for raw_set in record.trim().split(';') {
                 let mut set = vec![];
                 for cube in raw_set.trim().split(',') {
-                    if let Some((n, c)) = cube.trim().split_once(' ') {
-                        let cube_count = n.trim().parse::<usize>()?;
-                        set.push(match c.trim() {
-                            "red" => Cube::Red(cube_count),
-                            "green" => Cube::Green(cube_count),
-                            "blue" => Cube::Blue(cube_count),
-                            _ => return err!("Unable to parse game recore: {:?}", s),
-                        });
+                    // every cube is `count colour`, a lone count or colour is malformed
+                    let Some((n, c)) = cube.trim().split_once(' ') else {
+                        return err!("Unable to parse cube {:?} in game record: {:?}", cube, s);
+                    };
+                    let cube_count = n.trim().parse::<usize>()?;
+                    let colour = c.trim();
+                    if colour.is_empty() || colour.contains(char::is_whitespace) {
+                        return err!("Unable to parse game record: {:?}", s);
                     }
+                    set.push((cube_count, colour.to_string()));
                 }
                 sets.push(set);
             }
@@ -57,22 +119,176 @@ impl FromStr for Game {
     }
 }
 
+// `Game N: a red, b blue; c green`, the sets and cubes in the recorded order
+impl std::fmt::Display for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sets: Vec<_> = self
+            .sets
+            .iter()
+            .map(|set| {
+                set.iter()
+                    .map(|(n, c)| format!("{n} {c}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .collect();
+        write!(f, "Game {}: {}", self.id, sets.join("; "))
+    }
+}
+
 impl Game {
-    fn power(&self) -> usize {
-        let mut red_min = 0;
-        let mut green_min = 0;
-        let mut blue_min = 0;
+    // merges the cubes of the same colour within a set and sorts the colours
+    fn normalise(&mut self) {
+        for set in &mut self.sets {
+            let mut bag = Bag::new();
+            for (n, c) in set.drain(..) {
+                *bag.entry(c).or_default() += n;
+            }
+            set.extend(bag.into_iter().map(|(c, n)| (n, c)));
+        }
+    }
 
+    // the fewest cubes of every colour seen in the game
+    fn min_bag(&self) -> Bag {
+        let mut bag = Bag::new();
         for set in &self.sets {
-            for c in set {
-                match c {
-                    Cube::Red(n) => red_min = red_min.max(*n),
-                    Cube::Green(n) => green_min = green_min.max(*n),
-                    Cube::Blue(n) => blue_min = blue_min.max(*n),
-                }
+            for (n, c) in set {
+                let min = bag.entry(c.clone()).or_default();
+                *min = (*min).max(*n);
+            }
+        }
+        bag
+    }
+
+    fn possible(&self, bag: &Bag) -> bool {
+        self.min_bag()
+            .iter()
+            .all(|(c, n)| n <= bag.get(c).unwrap_or(&0))
+    }
+
+    // the product of the fewest cubes of the colours, a colour the game has never shown counts 0
+    fn power(&self, colours: &BTreeSet<String>) -> usize {
+        let bag = self.min_bag();
+        colours
+            .iter()
+            .map(|c| bag.get(c).copied().unwrap_or(0))
+            .product()
+    }
+}
+
+fn colours(games: &[Game]) -> BTreeSet<String> {
+    games.iter().flat_map(|g| g.min_bag().into_keys()).collect()
+}
+
+// the bag with the fewest cubes that makes at least `k` games possible, ties go to the bag
+// found first with smaller counts of the earlier colours
+fn smallest_bag(games: &[Game], k: usize) -> Option<Bag> {
+    fn search(
+        mins: &[Vec<usize>],
+        candidates: Vec<usize>,
+        k: usize,
+        bag: &mut Vec<usize>,
+        best: &mut Option<(usize, Vec<usize>)>,
+    ) {
+        let total: usize = bag.iter().sum();
+        if best.as_ref().is_some_and(|(t, _)| total >= *t) {
+            return;
+        }
+        let colour = bag.len();
+        if colour == mins[0].len() {
+            *best = Some((total, bag.clone()));
+            return;
+        }
+        let mut counts: Vec<_> = candidates.iter().map(|&g| mins[g][colour]).collect();
+        counts.sort();
+        counts.dedup();
+        for n in counts {
+            let fit: Vec<_> = candidates
+                .iter()
+                .copied()
+                .filter(|&g| mins[g][colour] <= n)
+                .collect();
+            if fit.len() >= k {
+                bag.push(n);
+                search(mins, fit, k, bag, best);
+                bag.pop();
+            }
+        }
+    }
+
+    if k > games.len() {
+        return None;
+    }
+    let colours = colours(games);
+    if k == 0 || colours.is_empty() {
+        return Some(colours.into_iter().map(|c| (c, 0)).collect());
+    }
+    let mins: Vec<Vec<usize>> = games
+        .iter()
+        .map(|g| {
+            let bag = g.min_bag();
+            colours
+                .iter()
+                .map(|c| bag.get(c).copied().unwrap_or(0))
+                .collect()
+        })
+        .collect();
+    let mut best = None;
+    search(&mins, (0..games.len()).collect(), k, &mut vec![], &mut best);
+    best.map(|(_, counts)| colours.into_iter().zip(counts).collect())
+}
+
+#[derive(Debug)]
+enum Query {
+    Possible(Bag),
+    SmallestBag(usize),
+    Power,
+    Normalise,
+}
+
+impl Query {
+    fn answer(&self, games: &[Game]) -> String {
+        match self {
+            Query::Possible(bag) => {
+                let ids: Vec<_> = games
+                    .iter()
+                    .filter(|g| g.possible(bag))
+                    .map(|g| g.id)
+                    .collect();
+                format!(
+                    "possible with {}: {} games, ids {:?}, sum {}",
+                    display_bag(bag),
+                    ids.len(),
+                    ids,
+                    ids.iter().sum::<usize>()
+                )
             }
+            Query::SmallestBag(k) => match smallest_bag(games, *k) {
+                Some(bag) => format!(
+                    "smallest bag for {k} games: {} ({} cubes)",
+                    display_bag(&bag),
+                    bag.values().sum::<usize>()
+                ),
+                None => format!("no bag makes {k} of {} games possible", games.len()),
+            },
+            Query::Power => {
+                let colours = colours(games);
+                games
+                    .iter()
+                    .map(|g| format!("Game {}: {}", g.id, g.power(&colours)))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            Query::Normalise => games
+                .iter()
+                .map(|g| {
+                    let mut g = g.clone();
+                    g.normalise();
+                    g.to_string()
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
         }
-        red_min * green_min * blue_min
     }
 }
 
@@ -84,39 +300,21 @@ fn parse_input(input: &str) -> Result<Vec<Game>> {
         .collect()
 }
 
-fn part1(games: &[Game]) -> Result<usize> {
+fn part1(games: &[Game], trace: &mut Trace) -> Result<usize> {
     let start = Instant::now();
 
-    let red_max = 12;
-    let green_max = 13;
-    let blue_max = 14;
+    let bag = parse_bag("12 red, 13 green, 14 blue")?;
 
     let sum = games
         .iter()
-        .map(|g| {
-            for set in &g.sets {
-                for c in set {
-                    match c {
-                        Cube::Red(n) => {
-                            if n > &red_max {
-                                return 0usize;
-                            }
-                        }
-                        Cube::Green(n) => {
-                            if n > &green_max {
-                                return 0usize;
-                            }
-                        }
-                        Cube::Blue(n) => {
-                            if n > &blue_max {
-                                return 0usize;
-                            }
-                        }
-                    }
-                }
-            }
-            g.id
+        .filter(|g| {
+            let possible = g.possible(&bag);
+            trace.record("part1.game", || {
+                vec![("id", g.id.to_string()), ("possible", possible.to_string())]
+            });
+            possible
         })
+        .map(|g| g.id)
         .sum();
 
     writeln!(io::stdout(), "Part 1: {}", sum)?;
@@ -124,10 +322,25 @@ fn part1(games: &[Game]) -> Result<usize> {
     Ok(sum)
 }
 
-fn part2(games: &[Game]) -> Result<usize> {
+fn part2(games: &[Game], trace: &mut Trace) -> Result<usize> {
     let start = Instant::now();
 
-    let sum = games.iter().map(|g| g.power()).sum();
+    let colours = colours(games);
+    let sum = games
+        .iter()
+        .map(|g| {
+            let power = g.power(&colours);
+            // every colour the game has shown, whatever the colours are
+            trace.record("part2.game", || {
+                vec![
+                    ("id", g.id.to_string()),
+                    ("bag", display_bag(&g.min_bag())),
+                    ("power", power.to_string()),
+                ]
+            });
+            power
+        })
+        .sum();
 
     writeln!(io::stdout(), "Part 2: {}", sum)?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
@@ -135,13 +348,26 @@ fn part2(games: &[Game]) -> Result<usize> {
 }
 
 fn main() -> Result<()> {
+    let options = Options::from_args()?;
+    let mut trace = Trace::new(options.explain.is_some());
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
     let games = parse_input(&input)?;
 
-    part1(&games)?;
-    part2(&games)?;
+    if !options.queries.is_empty() {
+        for query in &options.queries {
+            writeln!(io::stdout(), "{}", query.answer(&games))?;
+        }
+        return Ok(());
+    }
+
+    part1(&games, &mut trace)?;
+    part2(&games, &mut trace)?;
+    if let Some(explain) = options.explain {
+        writeln!(io::stdout(), "{}", trace.report(explain))?;
+    }
     Ok(())
 }
 
@@ -152,10 +378,57 @@ Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
 Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
 Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
 Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
-    let games = parse_input(&input).unwrap();
+    let games = parse_input(input).unwrap();
+
+    assert_eq!(part1(&games, &mut Trace::default()).unwrap(), 8);
+    assert_eq!(part2(&games, &mut Trace::default()).unwrap(), 2286);
+
+    let mut trace = Trace::new(true);
+    part2(&games[..1], &mut trace).unwrap();
+    assert_eq!(
+        trace.report(Explain::Text),
+        "part2.game id=1 bag=6 blue, 2 green, 4 red power=48"
+    );
+
+    let bag = parse_bag("12 red, 13 green, 14 blue").unwrap();
+    assert_eq!(
+        Query::Possible(bag).answer(&games),
+        "possible with 14 blue, 13 green, 12 red: 3 games, ids [1, 2, 5], sum 8"
+    );
+    assert_eq!(
+        Query::Power.answer(&games),
+        "Game 1: 48\nGame 2: 12\nGame 3: 1560\nGame 4: 630\nGame 5: 36"
+    );
+    let smallest = |k| smallest_bag(&games, k).map(|b| display_bag(&b));
+    assert_eq!(smallest(0).unwrap(), "0 blue, 0 green, 0 red");
+    assert_eq!(smallest(1).unwrap(), "4 blue, 3 green, 1 red");
+    assert_eq!(smallest(3).unwrap(), "6 blue, 3 green, 6 red");
+    assert_eq!(smallest(5).unwrap(), "15 blue, 13 green, 20 red");
+    assert_eq!(smallest(6), None);
+}
 
-    assert_eq!(part1(&games).unwrap(), 8);
-    assert_eq!(part2(&games).unwrap(), 2286);
+#[test]
+fn other_colours() {
+    let games = parse_input("Game 1: 2 cyan, 1 magenta; 3 cyan\nGame 2: 1 yellow, 1 cyan").unwrap();
+    let colours = colours(&games);
+    assert_eq!(games[0].power(&colours), 0);
+    assert_eq!(
+        games[0].power(&["cyan".to_string(), "magenta".to_string()].into()),
+        3
+    );
+    assert!(games[1].possible(&parse_bag("1 yellow, 2 cyan").unwrap()));
+    assert!(!games[0].possible(&parse_bag("1 yellow, 2 cyan, 1 magenta").unwrap()));
+    assert_eq!(
+        display_bag(&smallest_bag(&games, 1).unwrap()),
+        "1 cyan, 0 magenta, 1 yellow"
+    );
+    let mut trace = Trace::new(true);
+    part2(&games, &mut trace).unwrap();
+    assert_eq!(
+        trace.report(Explain::Text),
+        "part2.game id=1 bag=3 cyan, 1 magenta power=0\n\
+         part2.game id=2 bag=1 cyan, 1 yellow power=0"
+    );
 }
 
 #[test]
@@ -163,6 +436,66 @@ fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
     let games = parse_input(&input).unwrap();
 
-    assert_eq!(part1(&games).unwrap(), 1734);
-    assert_eq!(part2(&games).unwrap(), 70387);
+    assert_eq!(part1(&games, &mut Trace::default()).unwrap(), 1734);
+    assert_eq!(part2(&games, &mut Trace::default()).unwrap(), 70387);
+}
+
+#[test]
+fn display_round_trip() {
+    let mut game: Game = "Game 7: 2 red, 1 blue, 3 red; 4 green;  1 blue ,5 blue"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        game.to_string(),
+        "Game 7: 2 red, 1 blue, 3 red; 4 green; 1 blue, 5 blue"
+    );
+    game.normalise();
+    assert_eq!(game.to_string(), "Game 7: 1 blue, 5 red; 4 green; 6 blue");
+    for bad in [
+        "Game 7: 3",
+        "Game 7: red",
+        "Game 7: 2 red, blue",
+        "Game 7: 2 red, 3; 4 green",
+        "Game 7: 2 red,",
+        "Game 7: 2 red;",
+    ] {
+        assert!(bad.parse::<Game>().is_err(), "{bad}");
+    }
+
+    let mut rng = xorshift::XorShift::new(0x9e37_79b9_7f4a_7c15);
+    let mut rand = |n: usize| rng.below(n as u64) as usize;
+    let colours = ["red", "green", "blue", "cyan", "magenta"];
+    for _ in 0..1000 {
+        let game = Game {
+            id: rand(1000),
+            sets: (0..1 + rand(5))
+                .map(|_| {
+                    (0..1 + rand(6))
+                        .map(|_| (rand(20), colours[rand(colours.len())].to_string()))
+                        .collect()
+                })
+                .collect(),
+        };
+        let parsed: Game = game.to_string().parse().unwrap();
+        assert_eq!(parsed, game);
+
+        let mut normalised = game.clone();
+        normalised.normalise();
+        assert_eq!(normalised.to_string().parse::<Game>().unwrap(), normalised);
+        let mut again = normalised.clone();
+        again.normalise();
+        assert_eq!(again, normalised);
+        for (set, merged) in game.sets.iter().zip(&normalised.sets) {
+            assert!(merged.windows(2).all(|w| w[0].1 < w[1].1));
+            for (_, c) in merged {
+                let count = |s: &[(usize, String)]| {
+                    s.iter()
+                        .filter(|(_, x)| x == c)
+                        .map(|(n, _)| n)
+                        .sum::<usize>()
+                };
+                assert_eq!(count(set), count(merged));
+            }
+        }
+    }
 }
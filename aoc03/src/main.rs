@@ -1,8 +1,15 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::error::Error;
-use std::io::{self, Read, Write};
+use std::io::{self, BufRead, Read, Write};
+use std::iter::once;
+use std::ops::Range;
+use std::str::FromStr;
 use std::time::Instant;
 
+#[cfg(test)]
+#[path = "../../common/xorshift.rs"]
+mod xorshift;
+
 #[allow(unused_macros)]
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
@@ -10,144 +17,322 @@ macro_rules! err {
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
-type Coord = (i32, i32);
-
-type Engine = HashMap<Coord, EnginePart>;
+#[derive(Debug, Clone, PartialEq)]
+struct Number {
+    row: usize,
+    cols: Range<usize>,
+    value: u32,
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum EnginePart {
-    Number(u32),
-    Symbol(char),
+#[derive(Debug, Clone, PartialEq)]
+struct Symbol {
+    row: usize,
+    col: usize,
+    c: char,
 }
 
-impl EnginePart {
-    fn from_char(c: char) -> Option<EnginePart> {
-        if let Some(n) = c.to_digit(10) {
-            Some(EnginePart::Number(n))
-        } else if c != '.' {
-            Some(EnginePart::Symbol(c))
-        } else {
-            None
-        }
-    }
+#[derive(Debug, Default)]
+struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    // the indexes of the numbers around every symbol
+    adjacent: Vec<Vec<usize>>,
 }
 
-fn parse_input(input: &str) -> (Coord, Engine) {
-    let mut engine = Engine::new();
-    let mut bound = (0, 0);
-    for (i, row) in input
-        .trim()
-        .lines()
-        .filter(|l| !l.trim().is_empty())
-        .enumerate()
-    {
-        bound.0 = bound.0.max(i as i32 + 1);
-        for (j, c) in row.trim().chars().enumerate() {
-            if let Some(e) = EnginePart::from_char(c) {
-                engine.insert((i as i32, j as i32), e);
+impl FromStr for Schematic {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut schematic = Schematic::default();
+        for (row, line) in s.lines().filter(|l| !l.trim().is_empty()).enumerate() {
+            let mut number: Option<Number> = None;
+            for (col, c) in line.trim().chars().chain(once('.')).enumerate() {
+                if let Some(d) = c.to_digit(10) {
+                    let n = number.get_or_insert(Number {
+                        row,
+                        cols: col..col,
+                        value: 0,
+                    });
+                    n.cols.end = col + 1;
+                    n.value = n
+                        .value
+                        .checked_mul(10)
+                        .and_then(|v| v.checked_add(d))
+                        .ok_or(format!("number too large at row {row}, column {col}"))?;
+                    continue;
+                }
+                schematic.numbers.extend(number.take());
+                if c != '.' {
+                    schematic.symbols.push(Symbol { row, col, c });
+                }
             }
-            bound.1 = bound.1.max(j as i32 + 1);
         }
-    }
-    (bound, engine)
-}
-
-fn adjacent(c: &Coord) -> [Coord; 8] {
-    [
-        (c.0 - 1, c.1),
-        (c.0 + 1, c.1),
-        (c.0, c.1 - 1),
-        (c.0, c.1 + 1),
-        (c.0 - 1, c.1 - 1),
-        (c.0 - 1, c.1 + 1),
-        (c.0 + 1, c.1 - 1),
-        (c.0 + 1, c.1 + 1),
-    ]
-}
-
-fn is_coord_valid(c: &Coord, bound: &Coord) -> bool {
-    c.0 >= 0 && c.1 >= 0 && c.0 < bound.0 && c.1 < bound.1
-}
-
-fn dfs(
-    c: Coord,
-    engine: &Engine,
-    bound: &Coord,
-    visited: &mut HashSet<Coord>,
-) -> (bool, Option<HashSet<Coord>>, u32) {
-    if visited.insert(c) {
-        if let Some(EnginePart::Number(n)) = engine.get(&c) {
-            let mut adjacent_gears = HashSet::new();
-            let mut flag = false;
-            for next_c in adjacent(&c) {
-                if let Some(EnginePart::Symbol(c)) = engine.get(&next_c) {
-                    if c == &'*' {
-                        adjacent_gears.insert(next_c);
+
+        let at: HashMap<_, _> = schematic
+            .symbols
+            .iter()
+            .enumerate()
+            .map(|(i, s)| ((s.row, s.col), i))
+            .collect();
+        schematic.adjacent = vec![vec![]; schematic.symbols.len()];
+        for (i, n) in schematic.numbers.iter().enumerate() {
+            for row in n.row.saturating_sub(1)..=n.row + 1 {
+                for col in n.cols.start.saturating_sub(1)..=n.cols.end {
+                    if let Some(&s) = at.get(&(row, col)) {
+                        schematic.adjacent[s].push(i);
                     }
-                    flag = true;
                 }
             }
-            if is_coord_valid(&(c.0, c.1 - 1), bound) {
-                let (next_flag, next_gears, next_sum) = dfs((c.0, c.1 - 1), engine, bound, visited);
-                let sum = n + 10 * next_sum;
-                if let Some(next_gears) = next_gears {
-                    adjacent_gears.extend(next_gears);
+        }
+        Ok(schematic)
+    }
+}
+
+impl Schematic {
+    // the sum of the numbers next to at least one symbol of the class
+    fn part_sum(&self, class: impl Fn(char) -> bool) -> u64 {
+        let mut parts = vec![false; self.numbers.len()];
+        for (s, numbers) in self.symbols.iter().zip(&self.adjacent) {
+            if class(s.c) {
+                for &n in numbers {
+                    parts[n] = true;
                 }
-                return (flag | next_flag, Some(adjacent_gears), sum);
-            } else {
-                return (flag, Some(adjacent_gears), *n);
             }
         }
+        self.numbers
+            .iter()
+            .zip(parts)
+            .filter(|(_, part)| *part)
+            .map(|(n, _)| n.value as u64)
+            .sum()
+    }
+
+    // every `c` symbol with exactly `k` numbers around it, with those numbers
+    fn gears(&self, c: char, k: usize) -> impl Iterator<Item = (&Symbol, Vec<u32>)> {
+        self.symbols
+            .iter()
+            .zip(&self.adjacent)
+            .filter(move |(s, numbers)| s.c == c && numbers.len() == k)
+            .map(|(s, numbers)| (s, numbers.iter().map(|&n| self.numbers[n].value).collect()))
+    }
+
+    // the sum of the ratios of the `*` symbols with exactly two numbers around them
+    fn gear_sum(&self) -> Result<u64> {
+        self.gears('*', 2).try_fold(0u64, |sum, (s, numbers)| {
+            sum.checked_add(ratio(&numbers)?)
+                .ok_or(format!("gear sum overflows at {},{}", s.row, s.col).into())
+        })
+    }
+
+    // the numbers touching no symbol
+    fn loose_numbers(&self) -> Vec<&Number> {
+        let mut parts = vec![false; self.numbers.len()];
+        for &n in self.adjacent.iter().flatten() {
+            parts[n] = true;
+        }
+        self.numbers
+            .iter()
+            .zip(parts)
+            .filter(|(_, part)| !part)
+            .map(|(n, _)| n)
+            .collect()
     }
-    (false, None, 0)
 }
 
-fn part1(bound: &Coord, engine: &Engine) -> Result<u32> {
-    let start = Instant::now();
+#[derive(Debug, Default)]
+struct Options {
+    queries: Vec<Query>,
+    stream: bool,
+}
+
+#[derive(Debug)]
+enum Query {
+    // the part sum of the symbols listed, all symbols if empty
+    Parts(String),
+    // the `*` symbols with exactly k numbers around them
+    Gears(usize),
+    Loose,
+}
 
-    // bfs or dfs?
-    let mut sum = 0;
-    let mut visited: HashSet<Coord> = HashSet::new();
-    for x in (0..bound.0).rev() {
-        for y in (0..bound.1).rev() {
-            if engine.contains_key(&(x, y)) && !visited.contains(&(x, y)) {
-                let (flag, _, whole_number) = dfs((x, y), engine, bound, &mut visited);
-                if flag {
-                    sum += whole_number;
+impl Options {
+    // `--parts [symbols]`, `--gears <k>` and `--loose` answer a query instead of the parts,
+    // `--stream` solves the parts row by row without loading the whole schematic
+    fn from_args() -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1).peekable();
+        while let Some(arg) = args.next() {
+            options.queries.push(match arg.as_str() {
+                "--parts" => {
+                    Query::Parts(args.next_if(|a| !a.starts_with("--")).unwrap_or_default())
                 }
-            }
+                "--gears" => match args.next() {
+                    Some(k) => Query::Gears(k.parse()?),
+                    None => return err!("missing the number of neighbours after --gears"),
+                },
+                "--loose" => Query::Loose,
+                "--stream" => {
+                    options.stream = true;
+                    continue;
+                }
+                _ => {
+                    return err!(
+                        "unknown argument {arg:?}, expect --parts [symbols], --gears <k>, \
+                         --loose or --stream"
+                    )
+                }
+            });
         }
+        Ok(options)
     }
+}
 
-    writeln!(io::stdout(), "Part 1: {sum}")?;
-    writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
-    Ok(sum)
+impl Query {
+    fn answer(&self, schematic: &Schematic) -> Result<String> {
+        Ok(match self {
+            Query::Parts(symbols) => {
+                let sum = schematic.part_sum(|c| symbols.is_empty() || symbols.contains(c));
+                format!("part sum next to {symbols:?}: {sum}")
+            }
+            Query::Gears(k) => schematic
+                .gears('*', *k)
+                .map(|(s, numbers)| {
+                    let ratio = ratio(&numbers)?;
+                    Ok(format!(
+                        "gear at {},{}: {numbers:?}, ratio {ratio}",
+                        s.row, s.col
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?
+                .join("\n"),
+            Query::Loose => schematic
+                .loose_numbers()
+                .iter()
+                .map(|n| format!("{} at {},{}..{}", n.value, n.row, n.cols.start, n.cols.end))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        })
+    }
 }
 
-fn part2(bound: &Coord, engine: &Engine) -> Result<u32> {
-    let start = Instant::now();
+// the product of the numbers around a gear
+fn ratio(numbers: &[u32]) -> Result<u64> {
+    numbers
+        .iter()
+        .try_fold(1u64, |ratio, &n| match ratio.checked_mul(n as u64) {
+            Some(ratio) => Ok(ratio),
+            None => err!("gear ratio of {numbers:?} overflows"),
+        })
+}
+
+// the columns and value of the number covering `col` of the row
+fn number_at(row: &[u8], col: usize) -> Result<Option<(Range<usize>, u32)>> {
+    if !row.get(col).is_some_and(u8::is_ascii_digit) {
+        return Ok(None);
+    }
+    let start = row[..col]
+        .iter()
+        .rposition(|c| !c.is_ascii_digit())
+        .map_or(0, |i| i + 1);
+    let end = row[col..]
+        .iter()
+        .position(|c| !c.is_ascii_digit())
+        .map_or(row.len(), |i| col + i);
+    let mut value: u32 = 0;
+    for c in &row[start..end] {
+        value = value
+            .checked_mul(10)
+            .and_then(|v| v.checked_add((c - b'0') as u32))
+            .ok_or(format!("number too large at column {start}"))?;
+    }
+    Ok(Some((start..end, value)))
+}
 
-    // bfs or dfs?
-    let mut visited: HashSet<Coord> = HashSet::new();
-    let mut gears = HashMap::new();
-    for x in (0..bound.0).rev() {
-        for y in (0..bound.1).rev() {
-            if engine.contains_key(&(x, y)) && !visited.contains(&(x, y)) {
-                let (_, next_gears, whole_number) = dfs((x, y), engine, bound, &mut visited);
-                if let Some(next_gears) = next_gears {
-                    for c in next_gears {
-                        gears.entry(c).or_insert(vec![]).push(whole_number);
+// the part numbers sum and gear ratios sum of the middle row of a window of three rows
+fn solve_row(window: &[Vec<u8>; 3]) -> Result<(u64, u64)> {
+    let is_symbol = |c: &u8| *c != b'.' && !c.is_ascii_digit();
+    let (mut part_sum, mut gear_sum) = (0u64, 0u64);
+    let row = &window[1];
+    let mut col = 0;
+    while col < row.len() {
+        if row[col] == b'*' {
+            let mut numbers = vec![];
+            for (r, above_or_below) in window.iter().enumerate() {
+                for c in col.saturating_sub(1)..=col + 1 {
+                    if let Some((cols, value)) = number_at(above_or_below, c)? {
+                        if !numbers.contains(&(r, cols.start, value)) {
+                            numbers.push((r, cols.start, value));
+                        }
                     }
                 }
             }
+            if numbers.len() == 2 {
+                let values: Vec<_> = numbers.iter().map(|n| n.2).collect();
+                gear_sum = match gear_sum.checked_add(ratio(&values)?) {
+                    Some(sum) => sum,
+                    None => return err!("gear sum overflows at column {col}"),
+                };
+            }
+        }
+        match number_at(row, col)? {
+            Some((cols, value)) => {
+                let border = cols.start.saturating_sub(1)..cols.end + 1;
+                if window
+                    .iter()
+                    .any(|r| r.iter().take(border.end).skip(border.start).any(is_symbol))
+                {
+                    part_sum += value as u64;
+                }
+                col = cols.end;
+            }
+            None => col += 1,
+        }
+    }
+    Ok((part_sum, gear_sum))
+}
+
+// both parts over a sliding window of three rows, so only three rows are ever held in memory
+fn solve_streaming(input: impl BufRead) -> Result<(u64, u64)> {
+    let mut rows = input
+        .lines()
+        .filter(|l| !l.as_ref().is_ok_and(|l| l.trim().is_empty()));
+    // the rows above, at and below the row being solved
+    let mut window: [Vec<u8>; 3] = Default::default();
+    let (mut part_sum, mut gear_sum) = (0u64, 0u64);
+    loop {
+        let next = rows.next().transpose()?;
+        let done = next.is_none();
+        window.rotate_left(1);
+        window[2] = next
+            .map(|l| l.trim().as_bytes().to_vec())
+            .unwrap_or_default();
+        if !window[1].is_empty() {
+            let (parts, gears) = solve_row(&window)?;
+            (part_sum, gear_sum) = match (part_sum.checked_add(parts), gear_sum.checked_add(gears))
+            {
+                (Some(parts), Some(gears)) => (parts, gears),
+                _ => return err!("part or gear sum overflows"),
+            };
+        }
+        if done {
+            return Ok((part_sum, gear_sum));
         }
     }
+}
+
+fn part1(schematic: &Schematic) -> Result<u64> {
+    let start = Instant::now();
 
-    let sum = gears
-        .values()
-        .filter(|v| v.len() > 1)
-        .map(|v| v.iter().product::<u32>())
-        .sum();
+    let sum = schematic.part_sum(|_| true);
+
+    writeln!(io::stdout(), "Part 1: {sum}")?;
+    writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
+    Ok(sum)
+}
+
+fn part2(schematic: &Schematic) -> Result<u64> {
+    let start = Instant::now();
+
+    let sum = schematic.gear_sum()?;
 
     writeln!(io::stdout(), "Part 2: {sum}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
@@ -155,13 +340,31 @@ fn part2(bound: &Coord, engine: &Engine) -> Result<u32> {
 }
 
 fn main() -> Result<()> {
+    let options = Options::from_args()?;
+
+    if options.stream {
+        let start = Instant::now();
+        let (part_sum, gear_sum) = solve_streaming(io::stdin().lock())?;
+        writeln!(io::stdout(), "Part 1: {part_sum}")?;
+        writeln!(io::stdout(), "Part 2: {gear_sum}")?;
+        writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
+        return Ok(());
+    }
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
-    let (bound, engine) = parse_input(&input);
+    let schematic = input.parse::<Schematic>()?;
 
-    part1(&bound, &engine)?;
-    part2(&bound, &engine)?;
+    if !options.queries.is_empty() {
+        for query in &options.queries {
+            writeln!(io::stdout(), "{}", query.answer(&schematic)?)?;
+        }
+        return Ok(());
+    }
+
+    part1(&schematic)?;
+    part2(&schematic)?;
     Ok(())
 }
 
@@ -177,18 +380,78 @@ fn example_input() {
 ......755.
 ...$.*....
 .664.598..";
-    let (bound, engine) = parse_input(input);
+    let schematic = input.parse::<Schematic>().unwrap();
 
-    assert_eq!(engine.get(&(0, 0)).unwrap(), &EnginePart::Number(4));
-    assert_eq!(part1(&bound, &engine).unwrap(), 4361);
-    assert_eq!(part2(&bound, &engine).unwrap(), 467835);
+    assert_eq!(
+        schematic.numbers[0],
+        Number {
+            row: 0,
+            cols: 0..3,
+            value: 467
+        }
+    );
+    assert_eq!(part1(&schematic).unwrap(), 4361);
+    assert_eq!(part2(&schematic).unwrap(), 467835);
+    assert_eq!(solve_streaming(input.as_bytes()).unwrap(), (4361, 467835));
+
+    assert_eq!(schematic.part_sum(|c| c == '#'), 633);
+    assert_eq!(schematic.part_sum(|c| c == '$' || c == '+'), 664 + 592);
+    assert_eq!(
+        Query::Gears(1).answer(&schematic).unwrap(),
+        "gear at 4,3: [617], ratio 617"
+    );
+    assert_eq!(
+        Query::Loose.answer(&schematic).unwrap(),
+        "114 at 0,5..8\n58 at 5,7..9"
+    );
+    assert_eq!(
+        ratio(&[u32::MAX, u32::MAX]).unwrap(),
+        u32::MAX as u64 * u32::MAX as u64
+    );
+    assert!(ratio(&[u32::MAX, u32::MAX, 2]).is_err());
+}
+
+#[test]
+fn streaming_matches_schematic() {
+    let mut rng = xorshift::XorShift::new(0x2545_f491_4f6c_dd1d);
+    let mut rand = |n: usize| rng.below(n as u64) as usize;
+    for _ in 0..2000 {
+        let mut input = String::new();
+        for _ in 0..rand(12) {
+            let mut row = String::new();
+            for _ in 0..1 + rand(12) {
+                match rand(6) {
+                    0 if !row.ends_with(|c: char| c.is_ascii_digit()) => {
+                        row += &rand(u32::MAX as usize + 1).to_string()
+                    }
+                    1 => row.push(['*', '#', '+', '$'][rand(4)]),
+                    2 if rand(3) == 0 => row.push('*'),
+                    _ => row.push('.'),
+                }
+            }
+            input += &row;
+            input.push('\n');
+        }
+        let schematic = input.parse::<Schematic>().unwrap();
+        // full size numbers can overflow the gear sum, both ways have to agree on that too
+        let expected = part1(&schematic).and_then(|sum| Ok((sum, part2(&schematic)?)));
+        assert_eq!(
+            solve_streaming(input.as_bytes()).ok(),
+            expected.ok(),
+            "{input}"
+        );
+    }
 }
 
 #[test]
 fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
-    let (bound, engine) = parse_input(&input);
+    let schematic = input.parse::<Schematic>().unwrap();
 
-    assert_eq!(part1(&bound, &engine).unwrap(), 540131);
-    assert_eq!(part2(&bound, &engine).unwrap(), 86879020);
+    assert_eq!(part1(&schematic).unwrap(), 540131);
+    assert_eq!(part2(&schematic).unwrap(), 86879020);
+    assert_eq!(
+        solve_streaming(input.as_bytes()).unwrap(),
+        (540131, 86879020)
+    );
 }
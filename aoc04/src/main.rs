@@ -1,6 +1,6 @@
-use std::collections::HashSet;
 use std::error::Error;
 use std::io::{self, Read, Write};
+use std::str::FromStr;
 use std::time::Instant;
 
 #[allow(unused_macros)]
@@ -10,95 +10,269 @@ macro_rules! err {
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
-type Card = (HashSet<usize>, Vec<usize>);
+#[derive(Debug, Clone, PartialEq)]
+enum CardError {
+    // the line is not `Card N: winning numbers | held numbers`
+    Malformed {
+        text: String,
+    },
+    // a number is not a number below 100
+    Number {
+        card: usize,
+        number: String,
+    },
+    // a number is twice in the winning or the held numbers
+    DuplicateNumber {
+        card: usize,
+        list: &'static str,
+        number: u32,
+    },
+    DuplicateId {
+        card: usize,
+    },
+    // the points of the matches do not fit in a usize
+    Points {
+        card: usize,
+        matches: usize,
+    },
+    // the copies won of a card do not fit in a usize
+    Copies {
+        card: usize,
+    },
+    // the cards are not numbered 1, 2, 3, ...
+    OutOfOrder {
+        card: usize,
+        expected: usize,
+    },
+}
 
-fn parse_input(input: &str) -> Result<Vec<Card>> {
-    let mut cards = vec![];
+impl std::fmt::Display for CardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CardError::Malformed { text } => write!(f, "Unable to parse card: {text:?}"),
+            CardError::Number { card, number } => {
+                write!(f, "card {card}: {number:?} is not a number below 100")
+            }
+            CardError::DuplicateNumber { card, list, number } => {
+                write!(f, "card {card}: {number} is twice in the {list} numbers")
+            }
+            CardError::DuplicateId { card } => write!(f, "card {card} appears twice"),
+            CardError::Points { card, matches } => {
+                write!(
+                    f,
+                    "card {card}: {matches} matches are worth too many points"
+                )
+            }
+            CardError::Copies { card } => write!(f, "card {card}: too many copies won"),
+            CardError::OutOfOrder { card, expected } => {
+                write!(f, "card {card} is where card {expected} is expected")
+            }
+        }
+    }
+}
 
-    for line in input.lines().filter(|l| !l.trim().is_empty()) {
-        if let Some((_, nums)) = line.trim().split_once(':') {
-            if let Some((win, have)) = nums.split_once('|') {
-                cards.push((
-                    win.split_whitespace()
-                        .map(|n| n.parse::<usize>().unwrap())
-                        .collect(),
-                    have.split_whitespace()
-                        .map(|n| n.parse::<usize>().unwrap())
-                        .collect(),
-                ));
-            } else {
-                return err!("Unable to parse card: {:?}", line);
+impl Error for CardError {}
+
+// winning and held numbers as bitsets, the numbers are below 100
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Scratchcard {
+    id: usize,
+    winning: u128,
+    held: u128,
+}
+
+impl FromStr for Scratchcard {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let malformed = || CardError::Malformed {
+            text: s.to_string(),
+        };
+        let (card, nums) = s.trim().split_once(':').ok_or_else(malformed)?;
+        let id = card
+            .strip_prefix("Card")
+            .and_then(|id| id.trim().parse::<usize>().ok())
+            .ok_or_else(malformed)?;
+        let (win, have) = nums.split_once('|').ok_or_else(malformed)?;
+
+        let bits = |nums: &str, list| -> Result<u128> {
+            let mut bits: u128 = 0;
+            for n in nums.split_whitespace() {
+                let number = match n.parse::<u32>() {
+                    Ok(number) if number < 100 => number,
+                    _ => {
+                        return Err(CardError::Number {
+                            card: id,
+                            number: n.to_string(),
+                        }
+                        .into())
+                    }
+                };
+                if bits & 1 << number != 0 {
+                    return Err(CardError::DuplicateNumber {
+                        card: id,
+                        list,
+                        number,
+                    }
+                    .into());
+                }
+                bits |= 1 << number;
             }
-        } else {
-            return err!("Unable to parse card: {:?}", line);
+            Ok(bits)
+        };
+        Ok(Scratchcard {
+            id,
+            winning: bits(win, "winning")?,
+            held: bits(have, "held")?,
+        })
+    }
+}
+
+impl Scratchcard {
+    fn matches(&self) -> usize {
+        (self.winning & self.held).count_ones() as usize
+    }
+
+    // 2^(n - 1) for n matches, an error from 65 matches on with a 64-bit usize
+    fn points(&self) -> Result<usize> {
+        match self.matches() {
+            0 => Ok(0),
+            n => 1usize.checked_shl(n as u32 - 1).ok_or_else(|| {
+                CardError::Points {
+                    card: self.id,
+                    matches: n,
+                }
+                .into()
+            }),
         }
     }
-    Ok(cards)
 }
 
-fn part1(cards: &[Card]) -> Result<usize> {
-    let start = Instant::now();
+// what to do with the copies won past the last card
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Overflow {
+    // they are dropped
+    Clamp,
+    // they are an error
+    Error,
+    // they are counted as cards without numbers after the last card, which only exist as
+    // the copies won
+    Extend,
+}
+
+impl FromStr for Overflow {
+    type Err = Box<dyn Error>;
 
-    let result = cards
-        .iter()
-        .map(|card| {
-            let mut count = 0;
-            for n1 in &card.0 {
-                if card.1.contains(n1) {
-                    count += 1;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "clamp" => Ok(Overflow::Clamp),
+            "error" => Ok(Overflow::Error),
+            "extend" => Ok(Overflow::Extend),
+            _ => err!("unknown overflow policy {s:?}, expect clamp, error or extend"),
+        }
+    }
+}
+
+// the number of copies of every card once all the won copies are scratched
+fn copies(cards: &[Scratchcard], overflow: Overflow) -> Result<Vec<usize>> {
+    let mut copies = vec![1usize; cards.len()];
+    for (i, card) in cards.iter().enumerate() {
+        let wins = i + 1..i + 1 + card.matches();
+        if wins.end > cards.len() {
+            match overflow {
+                Overflow::Clamp => {}
+                Overflow::Error => {
+                    return err!(
+                        "card {} wins copies of {} cards past the last card {}",
+                        i + 1,
+                        wins.end - cards.len(),
+                        cards.len()
+                    )
                 }
+                Overflow::Extend => copies.resize(copies.len().max(wins.end), 0),
             }
-            if count == 0 {
-                0
+        }
+        for j in wins.start..wins.end.min(copies.len()) {
+            copies[j] = copies[j]
+                .checked_add(copies[i])
+                .ok_or(CardError::Copies { card: j + 1 })?;
+        }
+    }
+    Ok(copies)
+}
+
+// the cards must be numbered 1, 2, 3, ... in order, as the copies are won by position
+fn parse_input(input: &str) -> Result<Vec<Scratchcard>> {
+    let mut cards: Vec<Scratchcard> = vec![];
+    for line in input.lines().filter(|l| !l.trim().is_empty()) {
+        let card: Scratchcard = line.parse()?;
+        let expected = cards.len() + 1;
+        if card.id != expected {
+            return Err(if cards.iter().any(|c| c.id == card.id) {
+                CardError::DuplicateId { card: card.id }
             } else {
-                2usize.pow(count - 1)
+                CardError::OutOfOrder {
+                    card: card.id,
+                    expected,
+                }
             }
-        })
-        .sum();
+            .into());
+        }
+        cards.push(card);
+    }
+    Ok(cards)
+}
+
+fn part1(cards: &[Scratchcard]) -> Result<usize> {
+    let start = Instant::now();
+
+    let mut result: usize = 0;
+    for card in cards {
+        result = match result.checked_add(card.points()?) {
+            Some(sum) => sum,
+            None => return err!("the points overflow at card {}", card.id),
+        };
+    }
 
     writeln!(io::stdout(), "Part 1: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
     Ok(result)
 }
 
-fn part2(cards: &[Card]) -> Result<usize> {
+fn part2(cards: &[Scratchcard], overflow: Overflow) -> Result<usize> {
     let start = Instant::now();
 
-    let each_card_wins: Vec<_> = cards
-        .iter()
-        .map(|card| {
-            let mut count = 0;
-            for n1 in &card.0 {
-                if card.1.contains(n1) {
-                    count += 1;
-                }
-            }
-            count
-        })
-        .collect();
-
-    let mut scratchcards = vec![1; cards.len()];
-    for i in 0..cards.len() {
-        for j in 1..=each_card_wins[i] {
-            scratchcards[i + j] += scratchcards[i];
-        }
+    let mut result: usize = 0;
+    for (i, copies) in copies(cards, overflow)?.into_iter().enumerate() {
+        result = match result.checked_add(copies) {
+            Some(sum) => sum,
+            None => return err!("the copies overflow at card {}", i + 1),
+        };
     }
 
-    let result = scratchcards.iter().sum();
-
     writeln!(io::stdout(), "Part 2: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
     Ok(result)
 }
 
 fn main() -> Result<()> {
+    // `--overflow clamp|error|extend`, error by default
+    let mut overflow = Overflow::Error;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--overflow", Some(policy)) => overflow = policy.parse()?,
+            _ => return err!("unknown argument {arg:?}, expect --overflow clamp|error|extend"),
+        }
+    }
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
     let cards = parse_input(&input)?;
 
     part1(&cards)?;
-    part2(&cards)?;
+    part2(&cards, overflow)?;
     Ok(())
 }
 
@@ -114,7 +288,131 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
     let cards = parse_input(input).unwrap();
 
     assert_eq!(part1(&cards).unwrap(), 13);
-    assert_eq!(part2(&cards).unwrap(), 30);
+    assert_eq!(part2(&cards, Overflow::Error).unwrap(), 30);
+    assert_eq!(
+        copies(&cards, Overflow::Error).unwrap(),
+        [1, 2, 4, 8, 14, 1]
+    );
+}
+
+#[test]
+fn copies_past_the_end() {
+    let input = "Card 1: 1 | 1 2 3
+Card 2: 1 2 3 | 1 2 3";
+    let cards = parse_input(input).unwrap();
+    assert_eq!(cards[1].matches(), 3);
+    assert_eq!(copies(&cards, Overflow::Clamp).unwrap(), [1, 2]);
+    assert_eq!(copies(&cards, Overflow::Extend).unwrap(), [1, 2, 2, 2, 2]);
+    assert_eq!(
+        copies(&cards, Overflow::Error).unwrap_err().to_string(),
+        "card 2 wins copies of 3 cards past the last card 2"
+    );
+}
+
+#[test]
+fn too_many_points() {
+    let card = |id: usize, matches: usize| {
+        let numbers: Vec<_> = (0..matches).map(|n| n.to_string()).collect();
+        let numbers = numbers.join(" ");
+        format!("Card {id}: {numbers} | {numbers}")
+    };
+    let cards = parse_input(&card(1, 64)).unwrap();
+    assert_eq!(cards[0].points().unwrap(), 1 << 63);
+    let cards = parse_input(&card(1, 65)).unwrap();
+    assert_eq!(
+        *cards[0]
+            .points()
+            .unwrap_err()
+            .downcast::<CardError>()
+            .unwrap(),
+        CardError::Points {
+            card: 1,
+            matches: 65
+        }
+    );
+    let cards = parse_input(&format!("{}\n{}", card(1, 64), card(2, 64))).unwrap();
+    assert!(part1(&cards).is_err());
+}
+
+#[test]
+fn too_many_copies() {
+    // every card wins copies of all the cards after it, so card k has 2^(k - 1) copies
+    let cards = |n: usize| {
+        let lines: Vec<_> = (1..=n)
+            .map(|id| {
+                let numbers: Vec<_> = (0..n - id).map(|n| n.to_string()).collect();
+                let numbers = numbers.join(" ");
+                format!("Card {id}: {numbers} | {numbers}")
+            })
+            .collect();
+        parse_input(&lines.join("\n")).unwrap()
+    };
+    let copies64 = copies(&cards(64), Overflow::Error).unwrap();
+    assert_eq!(copies64[63], 1 << 63);
+    // the copies fit and so does their sum 2^64 - 1
+    assert_eq!(part2(&cards(64), Overflow::Error).unwrap(), usize::MAX);
+    assert_eq!(
+        *copies(&cards(65), Overflow::Error)
+            .unwrap_err()
+            .downcast::<CardError>()
+            .unwrap(),
+        CardError::Copies { card: 65 }
+    );
+    assert!(part2(&cards(65), Overflow::Error).is_err());
+}
+
+#[test]
+fn invalid_cards() {
+    let error = |input| {
+        parse_input(input)
+            .unwrap_err()
+            .downcast::<CardError>()
+            .map(|e| *e)
+            .unwrap()
+    };
+    assert_eq!(
+        error("Card 1: 1 2 | 3\nCard 1: 1 | 2"),
+        CardError::DuplicateId { card: 1 }
+    );
+    assert_eq!(
+        error("Card 1: 1 2 | 3\nCard 3: 1 | 2"),
+        CardError::OutOfOrder {
+            card: 3,
+            expected: 2
+        }
+    );
+    assert_eq!(
+        error("Card 2: 1 2 | 3"),
+        CardError::OutOfOrder {
+            card: 2,
+            expected: 1
+        }
+    );
+    assert_eq!(
+        error("Card 1: 1 2 1 | 3"),
+        CardError::DuplicateNumber {
+            card: 1,
+            list: "winning",
+            number: 1
+        }
+    );
+    assert_eq!(
+        error("Card 1: 1 2 | 3 3").to_string(),
+        "card 1: 3 is twice in the held numbers"
+    );
+    assert_eq!(
+        error("Card 1: 100 | 1"),
+        CardError::Number {
+            card: 1,
+            number: "100".to_string()
+        }
+    );
+    assert_eq!(
+        error("1: 1 | 1"),
+        CardError::Malformed {
+            text: "1: 1 | 1".to_string()
+        }
+    );
 }
 
 #[test]
@@ -122,5 +420,5 @@ fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
     let cards = parse_input(&input).unwrap();
     assert_eq!(part1(&cards).unwrap(), 24733);
-    assert_eq!(part2(&cards).unwrap(), 5422730);
+    assert_eq!(part2(&cards, Overflow::Error).unwrap(), 5422730);
 }
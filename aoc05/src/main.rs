@@ -1,7 +1,13 @@
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
 use std::io::{self, Read, Write};
 use std::time::Instant;
 
+#[path = "../../common/explain.rs"]
+mod explain;
+
+use explain::{Explain, Trace};
+
 #[allow(unused_macros)]
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
@@ -9,15 +15,62 @@ macro_rules! err {
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
+#[derive(Debug, Default)]
+struct Options {
+    explain: Option<Explain>,
+    // the locations to find the seeds of
+    seeds_for: Vec<Number>,
+    // (from, to, value) conversions between two categories
+    conversions: Vec<(String, String, Number)>,
+}
+
+impl Options {
+    // `--explain` prints the trace as text, `--explain=json` as json,
+    // `--seeds-for <location>` prints the seeds that end at the location,
+    // `--convert <from> <to> <value>` converts a value between two categories
+    fn from_args() -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing a value of {arg}"));
+            match arg.as_str() {
+                "--seeds-for" => options.seeds_for.push(value()?.parse()?),
+                "--convert" => {
+                    let (from, to) = (value()?, value()?);
+                    options.conversions.push((from, to, value()?.parse()?));
+                }
+                _ => match Explain::from_arg(&arg) {
+                    Some(explain) => options.explain = Some(explain),
+                    None => {
+                        return err!(
+                            "unknown argument {arg:?}, expect --explain[=text|json], \
+                             --seeds-for <location> or --convert <from> <to> <value>"
+                        )
+                    }
+                },
+            }
+        }
+        Ok(options)
+    }
+}
+
 type Number = i64;
 
 type SingleMap = (Number, Number, Number); // dest, src, length
 type Range = (Number, Number); // [start, end)
 
+// the lines under a `from-to-to map:` header
+#[derive(Debug)]
+struct CategoryMap {
+    from: String,
+    to: String,
+    lines: Vec<SingleMap>,
+}
+
 #[derive(Debug)]
 struct Almanac {
     seeds: Vec<Number>,
-    maps: Vec<Vec<SingleMap>>,
+    maps: Vec<CategoryMap>,
 }
 
 impl Almanac {
@@ -28,69 +81,256 @@ impl Almanac {
         }
     }
 
-    fn append_maps(&mut self, maps: Vec<SingleMap>) {
-        self.maps.push(maps);
+    // a map must start from `seed` or from a category an earlier map goes to
+    fn append_map(&mut self, map: CategoryMap) -> Result<()> {
+        if map.from != "seed" && !self.maps.iter().any(|m| m.to == map.from) {
+            return err!(
+                "{}-to-{} map is out of order, no earlier map goes to {}",
+                map.from,
+                map.to,
+                map.from
+            );
+        }
+        if self
+            .maps
+            .iter()
+            .any(|m| m.from == map.from && m.to == map.to)
+        {
+            return err!("{}-to-{} map appears twice", map.from, map.to);
+        }
+        let mut sources: Vec<_> = map
+            .lines
+            .iter()
+            .map(|&(_, src, len)| (src, src + len))
+            .collect();
+        sources.sort();
+        if let Some(w) = sources.windows(2).find(|w| w[0].1 > w[1].0) {
+            return err!(
+                "{}-to-{} map has overlapping source ranges [{},{}) and [{},{})",
+                map.from,
+                map.to,
+                w[0].0,
+                w[0].1,
+                w[1].0,
+                w[1].1
+            );
+        }
+        self.maps.push(map);
+        Ok(())
+    }
+
+    // the maps on the shortest path of categories from `from` to `to`
+    fn path(&self, from: &str, to: &str) -> Result<Vec<&CategoryMap>> {
+        let mut previous: HashMap<&str, Option<&CategoryMap>> = HashMap::from([(from, None)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                let mut path = vec![];
+                let mut at = to;
+                while let Some(Some(map)) = previous.get(at) {
+                    path.push(*map);
+                    at = &map.from;
+                }
+                path.reverse();
+                return Ok(path);
+            }
+            for map in self.maps.iter().filter(|m| m.from == category) {
+                if !previous.contains_key(map.to.as_str()) {
+                    previous.insert(&map.to, Some(map));
+                    queue.push_back(&map.to);
+                }
+            }
+        }
+        err!("the chain of maps from {from} to {to} is broken")
+    }
+
+    // the chain from one category to another as one map
+    fn chain(&self, from: &str, to: &str) -> Result<IntervalMap> {
+        Ok(self
+            .path(from, to)?
+            .into_iter()
+            .fold(IntervalMap::default(), |chain, map| {
+                chain.then(&IntervalMap::from_maps(&map.lines))
+            }))
+    }
+
+    fn convert(&self, from: &str, to: &str, value: Number) -> Result<Number> {
+        Ok(self.chain(from, to)?.apply(value))
     }
 }
 
-fn parse_input(input: &str) -> Almanac {
+type Piece = (Number, Number, Number); // start, end, offset
+
+// a piecewise-linear map, a number in [start, end) of a piece maps to number + offset and a
+// number outside of all pieces maps to itself
+#[derive(Debug, Clone, Default, PartialEq)]
+struct IntervalMap {
+    // sorted, disjoint and non-empty, with adjacent pieces of different offsets
+    pieces: Vec<Piece>,
+}
+
+impl IntervalMap {
+    // the first map covering a number wins, as in the almanac
+    fn from_maps(maps: &[SingleMap]) -> Self {
+        let mut map = IntervalMap::default();
+        for &(dest, src, length) in maps.iter().rev() {
+            map.overlay((src, src + length, dest - src));
+        }
+        map
+    }
+
+    fn normalise(mut pieces: Vec<Piece>) -> Vec<Piece> {
+        pieces.retain(|&(s, e, o)| s < e && o != 0);
+        pieces.sort();
+        let mut merged: Vec<Piece> = vec![];
+        for p in pieces {
+            match merged.last_mut() {
+                Some(last) if last.1 == p.0 && last.2 == p.2 => last.1 = p.1,
+                _ => merged.push(p),
+            }
+        }
+        merged
+    }
+
+    // replaces the map over the range of the piece
+    fn overlay(&mut self, piece: Piece) {
+        let (start, end, _) = piece;
+        let mut pieces = vec![piece];
+        for &(s, e, o) in &self.pieces {
+            pieces.push((s, e.min(start), o));
+            pieces.push((s.max(end), e, o));
+        }
+        self.pieces = IntervalMap::normalise(pieces);
+    }
+
+    // the pieces over [start, end), the gaps between them included with an offset of 0
+    fn partition(&self, start: Number, end: Number) -> Vec<Piece> {
+        let mut parts = vec![];
+        let mut at = start;
+        let first = self.pieces.partition_point(|p| p.1 <= start);
+        for &(s, e, o) in self.pieces[first..].iter().take_while(|p| p.0 < end) {
+            if at < s {
+                parts.push((at, s, 0));
+            }
+            parts.push((at.max(s), e.min(end), o));
+            at = e.min(end);
+        }
+        if at < end {
+            parts.push((at, end, 0));
+        }
+        parts
+    }
+
+    fn apply(&self, x: Number) -> Number {
+        let i = self.pieces.partition_point(|p| p.1 <= x);
+        match self.pieces.get(i) {
+            Some(&(s, _, o)) if s <= x => x + o,
+            _ => x,
+        }
+    }
+
+    // the sorted and merged image of [start, end)
+    fn image(&self, range: Range) -> Vec<Range> {
+        merge_ranges(
+            self.partition(range.0, range.1)
+                .into_iter()
+                .map(|(s, e, o)| (s + o, e + o))
+                .collect(),
+        )
+    }
+
+    // `self` and then `next`
+    fn then(&self, next: &IntervalMap) -> IntervalMap {
+        let pieces = self.pieces.iter().chain(&next.pieces);
+        let (Some(lo), Some(hi)) = (pieces.clone().map(|p| p.0).min(), pieces.map(|p| p.1).max())
+        else {
+            return IntervalMap::default();
+        };
+        // outside of [lo, hi) both maps are the identity
+        let mut composed = vec![];
+        for (s, e, o) in self.partition(lo, hi) {
+            for (t, f, p) in next.partition(s + o, e + o) {
+                composed.push((t - o, f - o, o + p));
+            }
+        }
+        IntervalMap {
+            pieces: IntervalMap::normalise(composed),
+        }
+    }
+
+    // every x that maps to y, sorted
+    fn inverse(&self, y: Number) -> Vec<Number> {
+        let mut xs: Vec<_> = self
+            .pieces
+            .iter()
+            .filter(|&&(s, e, o)| s <= y - o && y - o < e)
+            .map(|&(_, _, o)| y - o)
+            .collect();
+        if !self.pieces.iter().any(|&(s, e, _)| s <= y && y < e) {
+            xs.push(y);
+        }
+        xs.sort();
+        xs
+    }
+}
+
+fn parse_input(input: &str) -> Result<Almanac> {
     let mut almanac = Almanac::new();
-    let mut maps = vec![];
-    for line in input.lines() {
-        if line.trim().is_empty() && !maps.is_empty() {
-            almanac.append_maps(maps.clone());
-            maps.clear();
-        } else if let Some(seeds) = line.trim().strip_prefix("seeds: ") {
+    let mut map: Option<CategoryMap> = None;
+    for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(seeds) = line.strip_prefix("seeds:") {
             almanac.seeds = seeds
                 .split_whitespace()
-                .map(|n| n.parse::<Number>().unwrap())
-                .collect();
-        } else if line.contains("map") {
-            continue;
+                .map(|n| n.parse::<Number>())
+                .collect::<std::result::Result<_, _>>()?;
+        } else if let Some(header) = line.strip_suffix(" map:") {
+            let Some((from, to)) = header.split_once("-to-") else {
+                return err!("Unable to parse map header: {line:?}");
+            };
+            if let Some(map) = map.take() {
+                almanac.append_map(map)?;
+            }
+            map = Some(CategoryMap {
+                from: from.to_string(),
+                to: to.to_string(),
+                lines: vec![],
+            });
         } else {
-            let mut parts = line.split_whitespace();
+            let Some(map) = map.as_mut() else {
+                return err!("map line before any map header: {line:?}");
+            };
+            let mut parts = line.split_whitespace().map(|n| n.parse::<Number>());
             if let (Some(dest), Some(src), Some(length), None) =
                 (parts.next(), parts.next(), parts.next(), parts.next())
             {
-                maps.push((
-                    dest.parse::<Number>().unwrap(),
-                    src.parse::<Number>().unwrap(),
-                    length.parse::<Number>().unwrap(),
-                ))
+                map.lines.push((dest?, src?, length?));
+            } else {
+                return err!("Unable to parse map line: {line:?}");
             }
         }
     }
-    if !maps.is_empty() {
-        almanac.append_maps(maps);
+    if let Some(map) = map {
+        almanac.append_map(map)?;
     }
-    almanac
+    Ok(almanac)
 }
 
-fn convert(input: Number, dest: Number, src: Number, length: Number) -> Option<Number> {
-    if input < src || input >= src + length {
-        None
-    } else {
-        Some(input - src + dest)
-    }
-}
-
-fn part1(almanac: &Almanac) -> Result<Number> {
+fn part1(almanac: &Almanac, trace: &mut Trace) -> Result<Number> {
     let start = Instant::now();
 
+    let chain = almanac.chain("seed", "location")?;
     let result = almanac
         .seeds
         .iter()
-        .map(|seed| {
-            let mut start = *seed;
-            for maps in &almanac.maps {
-                if let Some(next) = maps
-                    .iter()
-                    .find_map(|&(dest, src, length)| convert(start, dest, src, length))
-                {
-                    start = next
-                }
-            }
-            start
+        .map(|&seed| {
+            let location = chain.apply(seed);
+            trace.record("part1.seed", || {
+                vec![
+                    ("seed", seed.to_string()),
+                    ("location", location.to_string()),
+                ]
+            });
+            location
         })
         .min()
         .unwrap();
@@ -133,67 +373,71 @@ fn merge_ranges(mut ranges: Vec<Range>) -> Vec<Range> {
     merged_ranges
 }
 
-fn convert_range(
-    input: Range,
-    dest: Number,
-    src: Number,
-    length: Number,
-) -> (Vec<Range>, Option<Range>) {
-    // src range: src..src+length
-    // src and dest offset is: dest - src
-    // then: dest = src + offset
-    let offset = dest - src;
-
-    let src_end = src + length;
-    let (start, end) = input;
-    if end <= src || src_end <= start {
-        (vec![input], None)
-    } else {
-        let overlaps = Some((start.max(src) + offset, end.min(src_end) + offset));
-        // input range overlaps with range Number::MIN..src and range src_end..Number::MAX
-        // is the remain range of input
-        let remain_range: Vec<Range> = [(start, end.min(src)), (start.max(src_end), end)]
-            .into_iter()
-            .filter(|(a, b)| a < b)
-            .collect();
-        (remain_range, overlaps)
-    }
-}
-
-fn convert_range_with_maps(range: Range, maps: &[SingleMap], converted: &mut Vec<Range>) {
-    if maps.is_empty() {
-        converted.push(range);
-        return;
-    }
-    let (dest, src, length) = maps[0];
-    let (r_ranges, overlaps) = convert_range(range, dest, src, length);
-    if let Some(overlaps) = overlaps {
-        converted.push(overlaps);
-    }
-    for r in r_ranges {
-        convert_range_with_maps(r, &maps[1..], converted);
+// how every layer of maps splits the seed ranges, a pass of its own as the answer only needs
+// the composed chain
+fn trace_split(almanac: &Almanac, ranges: &[Range], trace: &mut Trace) -> Result<()> {
+    let mut split = ranges.to_vec();
+    for (layer, map) in almanac.path("seed", "location")?.into_iter().enumerate() {
+        let map = IntervalMap::from_maps(&map.lines);
+        let mut next = vec![];
+        for &range in &split {
+            let converted = next.len();
+            next.extend(
+                map.partition(range.0, range.1)
+                    .into_iter()
+                    .map(|(s, e, o)| (s + o, e + o)),
+            );
+            trace.record("part2.split", || {
+                let into: Vec<_> = next[converted..]
+                    .iter()
+                    .map(|(s, e)| format!("[{s},{e})"))
+                    .collect();
+                vec![
+                    ("layer", layer.to_string()),
+                    ("range", format!("[{},{})", range.0, range.1)),
+                    ("into", into.join(" ")),
+                ]
+            });
+        }
+        split = next;
     }
+    Ok(())
 }
 
-fn part2(almanac: &Almanac) -> Result<Number> {
+fn part2(almanac: &Almanac, trace: &mut Trace) -> Result<Number> {
     let start = Instant::now();
 
-    let mut ranges: Vec<Range> = almanac
-        .seeds
-        .chunks(2)
-        .map(|chunks| (chunks[0], chunks[0] + chunks[1]))
-        .collect();
-    for maps in &almanac.maps {
-        let mut next_ranges = vec![];
-        for &range in &ranges {
-            convert_range_with_maps(range, maps, &mut next_ranges);
+    // an empty range holds no seed and has no image
+    let mut ranges: Vec<Range> = vec![];
+    for chunks in almanac.seeds.chunks(2).filter(|chunks| chunks[1] > 0) {
+        match chunks[0].checked_add(chunks[1]) {
+            Some(end) => ranges.push((chunks[0], end)),
+            None => return err!("seed range {} {} overflows", chunks[0], chunks[1]),
         }
-        ranges = merge_ranges(next_ranges);
+    }
 
-        // std::mem::swap(&mut ranges, &mut next_ranges);
+    let chain = almanac.chain("seed", "location")?;
+    if trace.enabled() {
+        trace_split(almanac, &ranges, trace)?;
     }
 
-    let result = ranges.into_iter().map(|(s, _)| s).min().unwrap();
+    let result = ranges
+        .iter()
+        .map(|&range| {
+            let image = chain.image(range);
+            trace.record("part2.range", || {
+                let into: Vec<_> = image.iter().map(|(s, e)| format!("[{s},{e})")).collect();
+                vec![
+                    ("range", format!("[{},{})", range.0, range.1)),
+                    ("into", into.join(" ")),
+                ]
+            });
+            image[0].0
+        })
+        .min();
+    let Some(result) = result else {
+        return err!("no seed range holds a seed");
+    };
 
     writeln!(io::stdout(), "Part 2: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
@@ -201,21 +445,44 @@ fn part2(almanac: &Almanac) -> Result<Number> {
 }
 
 fn main() -> Result<()> {
+    let options = Options::from_args()?;
+    let mut trace = Trace::new(options.explain.is_some());
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
-    let almanac = parse_input(&input);
+    let almanac = parse_input(&input)?;
 
-    part1(&almanac)?;
-    part2(&almanac)?;
+    if !options.seeds_for.is_empty() || !options.conversions.is_empty() {
+        let chain = almanac.chain("seed", "location")?;
+        for location in options.seeds_for {
+            writeln!(
+                io::stdout(),
+                "seeds for location {location}: {:?}",
+                chain.inverse(location)
+            )?;
+        }
+        for (from, to, value) in options.conversions {
+            let converted = almanac.convert(&from, &to, value)?;
+            writeln!(io::stdout(), "{from} {value} is {to} {converted}")?;
+        }
+        return Ok(());
+    }
+
+    part1(&almanac, &mut trace)?;
+    part2(&almanac, &mut trace)?;
+    if let Some(explain) = options.explain {
+        writeln!(io::stdout(), "{}", trace.report(explain))?;
+    }
     Ok(())
 }
 
 #[test]
 fn example_input() {
-    assert_eq!(convert(98, 50, 98, 2).unwrap(), 50);
-    assert_eq!(convert(99, 50, 98, 2).unwrap(), 51);
-    assert_eq!(convert(100, 50, 98, 2), None);
+    let map = IntervalMap::from_maps(&[(50, 98, 2)]);
+    assert_eq!(map.apply(98), 50);
+    assert_eq!(map.apply(99), 51);
+    assert_eq!(map.apply(100), 100);
     let input = "seeds: 79 14 55 13
 
 seed-to-soil map:
@@ -249,15 +516,111 @@ temperature-to-humidity map:
 humidity-to-location map:
 60 56 37
 56 93 4";
-    let almanac = parse_input(input);
-    assert_eq!(part1(&almanac).unwrap(), 35);
-    assert_eq!(part2(&almanac).unwrap(), 46);
+    let almanac = parse_input(input).unwrap();
+    assert_eq!(part1(&almanac, &mut Trace::default()).unwrap(), 35);
+    assert_eq!(part2(&almanac, &mut Trace::default()).unwrap(), 46);
+
+    let mut trace = Trace::new(true);
+    part2(&almanac, &mut trace).unwrap();
+    let report = trace.report(Explain::Text);
+    let steps = |step: &str| -> Vec<_> {
+        report
+            .lines()
+            .filter(|l| l.split(' ').next() == Some(step))
+            .collect()
+    };
+    let (split, range) = (steps("part2.split"), steps("part2.range"));
+    assert_eq!(
+        split.first(),
+        Some(&"part2.split layer=0 range=[79,93) into=[81,95)")
+    );
+    assert_eq!(
+        split.last(),
+        Some(&"part2.split layer=6 range=[90,99) into=[94,97) [56,60) [97,99)")
+    );
+    assert_eq!(
+        range,
+        [
+            "part2.range range=[79,93) into=[46,56) [60,61) [82,85)",
+            "part2.range range=[55,68) into=[56,60) [86,90) [94,99)"
+        ]
+    );
+
+    // an empty range is skipped, no range at all is an error
+    let empty = input.replacen("seeds: 79 14 55 13", "seeds: 79 14 55 0", 1);
+    let almanac = parse_input(&empty).unwrap();
+    assert_eq!(part2(&almanac, &mut Trace::default()).unwrap(), 46);
+    let empty = input.replacen("seeds: 79 14 55 13", "seeds: 79 0", 1);
+    let almanac = parse_input(&empty).unwrap();
+    assert!(part2(&almanac, &mut Trace::default()).is_err());
+    let huge = input.replacen("79 14", &format!("{} 2", Number::MAX), 1);
+    let almanac = parse_input(&huge).unwrap();
+    assert_eq!(
+        part2(&almanac, &mut Trace::default())
+            .unwrap_err()
+            .to_string(),
+        format!("seed range {} 2 overflows", Number::MAX)
+    );
+    let almanac = parse_input(input).unwrap();
+
+    // the composed chain agrees with walking every map, first match wins
+    let chain = almanac.chain("seed", "location").unwrap();
+    for seed in -10..120 {
+        let mut location = seed;
+        for map in &almanac.maps {
+            if let Some(&(dest, src, _)) = map
+                .lines
+                .iter()
+                .find(|&&(_, src, length)| src <= location && location < src + length)
+            {
+                location += dest - src;
+            }
+        }
+        assert_eq!(chain.apply(seed), location);
+    }
+    for location in 0..110 {
+        let seeds: Vec<_> = (-200..300)
+            .filter(|&x| chain.apply(x) == location)
+            .collect();
+        assert_eq!(chain.inverse(location), seeds);
+    }
+    assert_eq!(chain.inverse(46), [82]);
+
+    // seed 79 is soil 81, fertilizer 81, water 81, light 74, temperature 78, humidity 78
+    assert_eq!(almanac.convert("water", "humidity", 81).unwrap(), 78);
+    assert_eq!(almanac.convert("soil", "light", 81).unwrap(), 74);
+    assert!(almanac.convert("humidity", "water", 78).is_err());
+}
+
+#[test]
+fn invalid_almanac() {
+    let error = |input: &str| parse_input(input).unwrap_err().to_string();
+    assert_eq!(
+        error("seeds: 1\nseed-to-soil map:\n1 2 3\nwater-to-light map:\n1 2 3"),
+        "water-to-light map is out of order, no earlier map goes to water"
+    );
+    assert_eq!(
+        error("seeds: 1\nseed-to-soil map:\n50 98 2\n52 50 49"),
+        "seed-to-soil map has overlapping source ranges [50,99) and [98,100)"
+    );
+    assert_eq!(
+        error("seeds: 1\nseed-to-soil map:\n1 2 3\nseed-to-soil map:\n1 2 3"),
+        "seed-to-soil map appears twice"
+    );
+    assert!(parse_input("seeds: 1\n1 2 3").is_err());
+    assert!(parse_input("seeds: 1\nseed-soil map:\n1 2 3").is_err());
+
+    let almanac = parse_input("seeds: 1\nseed-to-soil map:\n1 2 3").unwrap();
+    assert_eq!(
+        almanac.chain("seed", "location").unwrap_err().to_string(),
+        "the chain of maps from seed to location is broken"
+    );
 }
 
 #[test]
 fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
-    let almanac = parse_input(&input);
-    assert_eq!(part1(&almanac).unwrap(), 424490994);
-    assert_eq!(part2(&almanac).unwrap(), 15290096);
+    let almanac = parse_input(&input).unwrap();
+    assert_eq!(part1(&almanac, &mut Trace::default()).unwrap(), 424490994);
+    assert_eq!(part2(&almanac, &mut Trace::default()).unwrap(), 15290096);
 }
@@ -0,0 +1,327 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use crate::first_win;
+
+macro_rules! err {
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
+}
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+// how far a boat goes in a race of `time` ms when the button is held for the first `hold` ms,
+// distances saturate at u128::MAX; displayed as it is parsed
+pub trait BoatModel: fmt::Debug + fmt::Display {
+    fn distance(&self, time: u128, hold: u128) -> u128;
+
+    // the exact outcome of a race, None when the model has no closed form
+    fn solve(&self, _time: u128, _record: u128) -> Option<Result<Race>> {
+        None
+    }
+}
+
+// every ms of holding adds `rate` to the speed, the puzzle rule is a rate of 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Linear {
+    pub rate: u128,
+}
+
+impl BoatModel for Linear {
+    fn distance(&self, time: u128, hold: u128) -> u128 {
+        self.rate
+            .saturating_mul(hold)
+            .saturating_mul(time.saturating_sub(hold))
+    }
+
+    fn solve(&self, time: u128, record: u128) -> Option<Result<Race>> {
+        // rate * x > record exactly when x > record / rate for a whole x
+        let first = match self.rate {
+            0 => None,
+            rate => first_win(time, record / rate),
+        };
+        // every hold goes nowhere without any speed
+        let best_hold = if self.rate == 0 { 0 } else { time / 2 };
+        // unlike `distance` this does not saturate, a wrong best distance is worse than none
+        let Some(best_distance) = self
+            .rate
+            .checked_mul(best_hold)
+            .and_then(|d| d.checked_mul(time - best_hold))
+        else {
+            return Some(err!(
+                "the best distance of {self} in a race of {time} ms overflows a u128"
+            ));
+        };
+        Some(Ok(Race {
+            wins: first.into_iter().map(|p| p..=time - p).collect(),
+            best_hold,
+            best_distance,
+            margin: best_distance.checked_sub(record).filter(|&m| m > 0),
+        }))
+    }
+}
+
+impl fmt::Display for Linear {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rate {
+            1 => write!(f, "linear"),
+            rate => write!(f, "accel:{rate}"),
+        }
+    }
+}
+
+// a linear charge that stops adding speed at `cap`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capped {
+    pub rate: u128,
+    pub cap: u128,
+}
+
+impl BoatModel for Capped {
+    fn distance(&self, time: u128, hold: u128) -> u128 {
+        let speed = self.rate.saturating_mul(hold).min(self.cap);
+        speed.saturating_mul(time.saturating_sub(hold))
+    }
+}
+
+impl fmt::Display for Capped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cap:{}:{}", self.rate, self.cap)
+    }
+}
+
+// a linear charge, the boat then loses `drag` speed every ms until it stops
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Drag {
+    pub rate: u128,
+    pub drag: u128,
+}
+
+impl BoatModel for Drag {
+    fn distance(&self, time: u128, hold: u128) -> u128 {
+        let speed = self.rate.saturating_mul(hold);
+        let left = time.saturating_sub(hold);
+        // the ms spent moving, the speed is speed - drag * k in the k-th of them
+        let moving = match self.drag {
+            0 => left,
+            drag => left.min(speed.div_ceil(drag)),
+        };
+        let lost = self
+            .drag
+            .saturating_mul(moving.saturating_mul(moving.saturating_sub(1)) / 2);
+        moving.saturating_mul(speed).saturating_sub(lost)
+    }
+}
+
+impl fmt::Display for Drag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "drag:{}:{}", self.rate, self.drag)
+    }
+}
+
+// `linear`, `accel:R`, `cap:R:C` or `drag:R:D`
+impl FromStr for Box<dyn BoatModel> {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split(':');
+        let kind = parts.next().unwrap_or_default();
+        let numbers = parts
+            .map(|n| n.parse())
+            .collect::<std::result::Result<Vec<u128>, _>>()?;
+        Ok(match (kind, &numbers[..]) {
+            ("linear", []) => Box::new(Linear { rate: 1 }),
+            ("accel", &[rate]) => Box::new(Linear { rate }),
+            ("cap", &[rate, cap]) => Box::new(Capped { rate, cap }),
+            ("drag", &[rate, drag]) => Box::new(Drag { rate, drag }),
+            _ => return err!("unknown model {s:?}, expect linear, accel:R, cap:R:C or drag:R:D"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Race {
+    // the hold times that beat the record
+    pub wins: Vec<RangeInclusive<u128>>,
+    // the shortest hold that goes the farthest
+    pub best_hold: u128,
+    pub best_distance: u128,
+    // how far the best hold goes past the record, None when it does not beat it
+    pub margin: Option<u128>,
+}
+
+impl fmt::Display for Race {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let wins: Vec<_> = self
+            .wins
+            .iter()
+            .map(|w| format!("{}..={}", w.start(), w.end()))
+            .collect();
+        write!(
+            f,
+            "wins=[{}] best_hold={} best_distance={} margin={}",
+            wins.join(", "),
+            self.best_hold,
+            self.best_distance,
+            self.margin.map_or("-".to_string(), |m| m.to_string())
+        )
+    }
+}
+
+pub fn race(model: &dyn BoatModel, time: u128, record: u128) -> Result<Race> {
+    model
+        .solve(time, record)
+        .unwrap_or_else(|| Ok(search(model, time, record)))
+}
+
+// a numeric solution for models whose distance rises then falls with the hold time
+pub fn search(model: &dyn BoatModel, time: u128, record: u128) -> Race {
+    let f = |hold| model.distance(time, hold);
+
+    // ternary search for the farthest hold, equal distances only happen around the top
+    let (mut lo, mut hi) = (0, time);
+    while hi - lo > 2 {
+        let (m1, m2) = (lo + (hi - lo) / 3, hi - (hi - lo) / 3);
+        if f(m1) < f(m2) {
+            lo = m1 + 1;
+        } else {
+            hi = m2;
+        }
+    }
+    let top = (lo..=hi).rev().max_by_key(|&p| f(p)).unwrap();
+    let best_distance = f(top);
+    // the search lands anywhere on a flat top, the distance never falls before it so the
+    // shortest best hold is the first to reach it
+    let (mut lo, mut hi) = (0, top);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if f(mid) == best_distance {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    let best_hold = lo;
+    let margin = best_distance.checked_sub(record).filter(|&m| m > 0);
+    if margin.is_none() {
+        return Race {
+            wins: vec![],
+            best_hold,
+            best_distance,
+            margin,
+        };
+    }
+
+    // the distance rises up to the best hold and falls after it
+    let (mut lo, mut hi) = (0, best_hold);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if f(mid) > record {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    let first = lo;
+    let (mut lo, mut hi) = (best_hold, time);
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        if f(mid) > record {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    Race {
+        wins: vec![first..=lo],
+        best_hold,
+        best_distance,
+        margin,
+    }
+}
+
+#[test]
+fn models_match_brute_force() {
+    fn brute(model: &dyn BoatModel, time: u128, record: u128) -> Race {
+        let distances: Vec<_> = (0..=time).map(|p| model.distance(time, p)).collect();
+        let best_distance = *distances.iter().max().unwrap();
+        let best_hold = distances.iter().position(|&d| d == best_distance).unwrap() as u128;
+        let mut wins: Vec<RangeInclusive<u128>> = vec![];
+        for p in (0..=time).filter(|&p| distances[p as usize] > record) {
+            match wins.last_mut() {
+                Some(w) if *w.end() + 1 == p => *w = *w.start()..=p,
+                _ => wins.push(p..=p),
+            }
+        }
+        Race {
+            wins,
+            best_hold,
+            best_distance,
+            margin: best_distance.checked_sub(record).filter(|&m| m > 0),
+        }
+    }
+
+    assert_eq!(Drag { rate: 2, drag: 3 }.distance(10, 4), 8 + 5 + 2);
+    assert_eq!(Drag { rate: 1, drag: 0 }.distance(7, 2), 10);
+    assert_eq!(Capped { rate: 3, cap: 10 }.distance(10, 5), 50);
+    for model in ["linear", "accel:3", "cap:3:10", "drag:2:3"] {
+        let parsed: Box<dyn BoatModel> = model.parse().unwrap();
+        assert_eq!(parsed.to_string(), model);
+    }
+    assert_eq!(
+        "accel:1".parse::<Box<dyn BoatModel>>().unwrap().to_string(),
+        "linear"
+    );
+    assert!("cap:1".parse::<Box<dyn BoatModel>>().is_err());
+    assert!("warp".parse::<Box<dyn BoatModel>>().is_err());
+
+    let mut rng = crate::xorshift::XorShift::new(0x2545_f491_4f6c_dd1d);
+    let mut rand = |n: u64| rng.below(n) as u128;
+    for _ in 0..2000 {
+        let time = rand(120);
+        let rate = rand(5);
+        let models: [Box<dyn BoatModel>; 3] = [
+            Box::new(Linear { rate }),
+            Box::new(Capped {
+                rate,
+                cap: rand(100),
+            }),
+            Box::new(Drag {
+                rate,
+                drag: rand(6),
+            }),
+        ];
+        for model in &models {
+            let top = (0..=time).map(|p| model.distance(time, p)).max().unwrap();
+            let record = rand(top as u64 + 2);
+            let expected = brute(model.as_ref(), time, record);
+            let (found, searched) = (
+                race(model.as_ref(), time, record).unwrap(),
+                search(model.as_ref(), time, record),
+            );
+            let case = format!("{model:?} time {time} record {record}");
+            assert_eq!(found.wins, expected.wins, "{case}");
+            assert_eq!(searched.wins, expected.wins, "{case}");
+            assert_eq!(found.best_hold, expected.best_hold, "{case}");
+            assert_eq!(searched.best_hold, expected.best_hold, "{case}");
+            assert_eq!(found.best_distance, expected.best_distance, "{case}");
+            assert_eq!(searched.best_distance, expected.best_distance, "{case}");
+            assert_eq!(searched.margin, expected.margin, "{case}");
+        }
+    }
+
+    // the puzzle rule agrees with the numeric search on huge races
+    let linear = Linear { rate: 1 };
+    let (time, record) = (10u128.pow(19) + 1, 10u128.pow(30));
+    assert_eq!(
+        race(&linear, time, record).unwrap(),
+        search(&linear, time, record)
+    );
+    // but the best distance past a u128 is an error rather than saturated
+    let fast = Linear { rate: 100 };
+    assert_eq!(
+        race(&fast, 10u128.pow(19), 0).unwrap_err().to_string(),
+        "the best distance of accel:100 in a race of 10000000000000000000 ms overflows a u128"
+    );
+}
@@ -2,27 +2,58 @@ use std::error::Error;
 use std::io::{self, Read, Write};
 use std::time::Instant;
 
-#[allow(unused_macros)]
+use boat::BoatModel;
+
+mod boat;
+#[cfg(test)]
+#[path = "../../common/xorshift.rs"]
+mod xorshift;
+
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
 }
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
-fn parse_input(input: &str) -> (Vec<usize>, Vec<usize>) {
+#[derive(Debug, Default)]
+struct Options {
+    models: Vec<Box<dyn BoatModel>>,
+}
+
+impl Options {
+    // `--model <model>` reports every race, and the long one, under a boat model
+    fn from_args() -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing a value of {arg}"));
+            match arg.as_str() {
+                "--model" => options.models.push(value()?.parse()?),
+                _ => {
+                    return err!(
+                        "unknown argument {arg:?}, expect --model linear|accel:R|cap:R:C|drag:R:D"
+                    )
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn parse_input(input: &str) -> (Vec<u128>, Vec<u128>) {
     let mut time = vec![];
     let mut distance = vec![];
     for line in input.lines().filter(|l| !l.trim().is_empty()) {
         if let Some(time_line) = line.strip_prefix("Time:") {
             time = time_line
                 .split_whitespace()
-                .map(|n| n.parse::<usize>().unwrap())
+                .map(|n| n.parse::<u128>().unwrap())
                 .collect();
         }
         if let Some(distance_line) = line.strip_prefix("Distance:") {
             distance = distance_line
                 .split_whitespace()
-                .map(|n| n.parse::<usize>().unwrap())
+                .map(|n| n.parse::<u128>().unwrap())
                 .collect();
         }
     }
@@ -30,39 +61,75 @@ fn parse_input(input: &str) -> (Vec<usize>, Vec<usize>) {
 }
 
 #[allow(dead_code)]
-fn get_distance(time: usize, pressed_time: usize) -> usize {
+fn get_distance(time: u128, pressed_time: u128) -> u128 {
     time.saturating_sub(pressed_time) * pressed_time
 }
 
-fn calc_distance_when_equal(t: usize, d: usize) -> usize {
+// a * b as the (high, low) halves of a 256 bits number
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const LOW: u128 = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & LOW);
+    let (b1, b0) = (b >> 64, b & LOW);
+    let (low, mid1, mid2, high) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+    let (mid, carry) = mid1.overflowing_add(mid2);
+    let (low, carry_low) = low.overflowing_add(mid << 64);
+    let high = high + (mid >> 64) + ((carry as u128) << 64) + carry_low as u128;
+    (high, low)
+}
+
+fn wins(time: u128, distance: u128, pressed_time: u128) -> bool {
+    mul_wide(time - pressed_time, pressed_time) > (0, distance)
+}
+
+// the largest s with s * s <= n, for an n below (2^128)^2 given as (high, low)
+fn isqrt_wide(n: (u128, u128)) -> u128 {
+    let (mut lo, mut hi) = (0, u128::MAX);
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        if mul_wide(mid, mid) <= n {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    lo
+}
+
+// the shortest pressed time that beats the distance, exact for any u128 time and distance
+fn first_win(t: u128, d: u128) -> Option<u128> {
     // (t - p) * p = d
     // p^2 - tp + d = 0
-    // p = (t + sqrt(t^2 -4d)) / 2
-    // p = (t - sqrt(t^2 -4d)) / 2
-    let (t, d) = (t as f64, d as f64);
-    let x1 = (t - (t * t - 4.0 * d).sqrt()) / 2.0;
-    let x2 = (t + (t * t - 4.0 * d).sqrt()) / 2.0;
-    // let start = if x1.ceil() == x1 {
-    //     (x1 + 1.0) as usize
-    // } else {
-    //     x1.ceil() as usize
-    // };
-    // let end = if x2.floor() == x2 {
-    //     (x2 - 1.0) as usize
-    // } else {
-    //     x2.floor() as usize
-    // };
-    // end - start + 1
-    (x2.ceil() - 1.0 - x1.floor() - 1.0) as usize + 1
-}
-
-fn part1(time: &[usize], distance: &[usize]) -> Result<usize> {
+    // p = (t - sqrt(t^2 - 4d)) / 2 or p = (t + sqrt(t^2 - 4d)) / 2
+    let (t2, four_d) = (mul_wide(t, t), mul_wide(d, 4));
+    if t2 < four_d {
+        return None;
+    }
+    let (low, borrow) = t2.1.overflowing_sub(four_d.1);
+    let root = isqrt_wide((t2.0 - four_d.0 - borrow as u128, low));
+    // isqrt rounds down, so the first winning time is at most a step away from this estimate
+    let mut first = (t - root.min(t)) / 2;
+    while first <= t / 2 && !wins(t, d, first) {
+        first += 1;
+    }
+    while first > 0 && wins(t, d, first - 1) {
+        first -= 1;
+    }
+    (first <= t / 2).then_some(first)
+}
+
+// the number of pressed times that beat the distance
+fn ways_to_win(t: u128, d: u128) -> u128 {
+    // (t - p) * p is symmetric around t / 2
+    first_win(t, d).map_or(0, |first| t - 2 * first + 1)
+}
+
+fn part1(time: &[u128], distance: &[u128]) -> Result<u128> {
     let start = Instant::now();
 
     let result = time
         .iter()
         .zip(distance.iter())
-        .map(|(t, d)| calc_distance_when_equal(*t, *d))
+        .map(|(t, d)| ways_to_win(*t, *d))
         .product();
 
     writeln!(io::stdout(), "Part 1: {result}")?;
@@ -70,22 +137,23 @@ fn part1(time: &[usize], distance: &[usize]) -> Result<usize> {
     Ok(result)
 }
 
-fn part2(time: &[usize], distance: &[usize]) -> Result<usize> {
+// the race read with the spaces between the numbers ignored
+fn long_race(time: &[u128], distance: &[u128]) -> (u128, u128) {
+    let join = |ns: &[u128]| {
+        ns.iter()
+            .map(|n| n.to_string())
+            .collect::<String>()
+            .parse::<u128>()
+            .unwrap()
+    };
+    (join(time), join(distance))
+}
+
+fn part2(time: &[u128], distance: &[u128]) -> Result<u128> {
     let start = Instant::now();
-    let time = time
-        .iter()
-        .map(|n| n.to_string())
-        .collect::<String>()
-        .parse::<usize>()
-        .unwrap();
-    let distance = distance
-        .iter()
-        .map(|n| n.to_string())
-        .collect::<String>()
-        .parse::<usize>()
-        .unwrap();
+    let (time, distance) = long_race(time, distance);
 
-    let result = calc_distance_when_equal(time, distance);
+    let result = ways_to_win(time, distance);
 
     writeln!(io::stdout(), "Part 2: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
@@ -93,11 +161,29 @@ fn part2(time: &[usize], distance: &[usize]) -> Result<usize> {
 }
 
 fn main() -> Result<()> {
+    let options = Options::from_args()?;
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
     let (time, distance) = parse_input(&input);
 
+    if !options.models.is_empty() {
+        let (long_time, long_distance) = long_race(&time, &distance);
+        for model in &options.models {
+            writeln!(io::stdout(), "{model}")?;
+            for (t, d) in time
+                .iter()
+                .zip(&distance)
+                .chain([(&long_time, &long_distance)])
+            {
+                let race = boat::race(model.as_ref(), *t, *d)?;
+                writeln!(io::stdout(), "  time={t} record={d} {race}")?;
+            }
+        }
+        return Ok(());
+    }
+
     part1(&time, &distance)?;
     part2(&time, &distance)?;
     Ok(())
@@ -116,6 +202,44 @@ Distance:  9  40  200";
     assert_eq!(part2(&time, &distance).unwrap(), 71503);
 }
 
+#[test]
+fn exact_solver() {
+    assert_eq!(mul_wide(u128::MAX, u128::MAX), (u128::MAX - 1, 1));
+    assert_eq!(isqrt_wide(mul_wide(u128::MAX, u128::MAX)), u128::MAX);
+    assert_eq!(isqrt_wide((0, 99)), 9);
+
+    let mut rng = xorshift::XorShift::new(0x2545_f491_4f6c_dd1d);
+    let mut rand = |n: u64| rng.below(n) as u128;
+    for _ in 0..2000 {
+        let t = rand(200);
+        let d = rand(t as u64 * t as u64 / 4 + 10);
+        let brute = (0..=t).filter(|&p| get_distance(t, p) > d).count() as u128;
+        assert_eq!(ways_to_win(t, d), brute, "time {t} distance {d}");
+    }
+
+    // a record set by holding p exactly is beaten by holding p + 1 to t - p - 1
+    let times = [
+        (1 << 53) + 1,
+        (1 << 64) + 7,
+        10u128.pow(30),
+        10u128.pow(30) - 1,
+    ];
+    for t in times {
+        let holds = [1, 2, 12_345, 10u128.pow(8) + 7, t / 3, t / 2 - 1];
+        // the records must fit in a u128 too
+        for p in holds.into_iter().filter(|p| p.checked_mul(t - p).is_some()) {
+            let d = get_distance(t, p);
+            assert_eq!(ways_to_win(t, d), t - 2 * p - 1, "time {t} hold {p}");
+            assert_eq!(ways_to_win(t, d - 1), t - 2 * p + 1, "time {t} hold {p}");
+        }
+    }
+    assert_eq!(ways_to_win(10u128.pow(19), u128::MAX), 0);
+    assert_eq!(
+        ways_to_win(10u128.pow(30), 10u128.pow(30)),
+        10u128.pow(30) - 3
+    );
+}
+
 #[test]
 fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
@@ -1,115 +1,257 @@
 use std::cmp::Ordering::*;
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::error::Error;
+use std::fmt;
 use std::io::{self, Read, Write};
+use std::str::FromStr;
 use std::time::Instant;
 
-#[allow(unused_macros)]
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
 }
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum HandKind {
-    Five,
-    Four,
-    Full,
-    Three,
-    Two,
-    One,
-    High,
+#[derive(Debug, Default)]
+struct Options {
+    rules: Vec<RuleSet>,
+    report: bool,
+    compare: Vec<(String, String)>,
+}
+
+impl Options {
+    // `--rules <rules>` prints the total winnings under house rules, e.g. `--rules "wild=J2"`,
+    // `--report` lists the ranked hands and `--compare <hand> <hand>` explains an order, under
+    // the rules given or those of both parts
+    fn from_args() -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing a value of {arg}"));
+            match arg.as_str() {
+                "--rules" => options.rules.push(value()?.parse()?),
+                "--report" => options.report = true,
+                "--compare" => {
+                    let a = value()?;
+                    options.compare.push((a, value()?));
+                }
+                _ => {
+                    return err!(
+                        "unknown argument {arg:?}, expect --rules <rules>, --report \
+                         or --compare <hand> <hand>"
+                    )
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+// the group sizes of a hand from the largest down, a kind beats another when it has the larger
+// group at the first size they differ in, e.g. [3, 2] (full house) beats [3, 1, 1]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct HandKind {
+    groups: Vec<usize>,
 }
 
 impl HandKind {
-    fn to_u8(self) -> u8 {
-        match self {
-            HandKind::Five => 6,
-            HandKind::Four => 5,
-            HandKind::Full => 4,
-            HandKind::Three => 3,
-            HandKind::Two => 2,
-            HandKind::One => 1,
-            HandKind::High => 0,
-        }
-    }
-
-    fn new(mut count: HashMap<i8, usize>) -> Result<Self> {
-        let j_count = count.remove(&-1).unwrap_or(0);
-        let mut values: Vec<_> = count.into_values().collect();
-        values.sort();
-        let count_number = values.iter().fold(0, |sum, i| sum * 10 + *i) + j_count;
-        Ok(match count_number {
-            5 => HandKind::Five,
-            14 => HandKind::Four,
-            23 => HandKind::Full,
-            113 => HandKind::Three,
-            122 => HandKind::Two,
-            1112 => HandKind::One,
-            11111 => HandKind::High,
-            _ => return err!("Wrong hand: {:?}", values),
-        })
+    // the group sizes of the plain cards, the wild cards join the largest group: any other
+    // use of them leaves a smaller or equal first group and so a lower kind
+    fn new(mut groups: Vec<usize>, wild: usize) -> Self {
+        groups.sort_by(|a, b| b.cmp(a));
+        match groups.first_mut() {
+            Some(largest) => *largest += wild,
+            None if wild > 0 => groups.push(wild),
+            None => {}
+        }
+        HandKind { groups }
     }
 }
 
-impl PartialOrd for HandKind {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+impl fmt::Display for HandKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.groups[..] {
+            [5] => write!(f, "five of a kind"),
+            [4, 1] => write!(f, "four of a kind"),
+            [3, 2] => write!(f, "full house"),
+            [3, 1, 1] => write!(f, "three of a kind"),
+            [2, 2, 1] => write!(f, "two pair"),
+            [2, 1, 1, 1] => write!(f, "one pair"),
+            [1, 1, 1, 1, 1] => write!(f, "high card"),
+            _ => {
+                let groups: Vec<_> = self.groups.iter().map(|g| g.to_string()).collect();
+                write!(f, "groups {}", groups.join("+"))
+            }
+        }
     }
 }
-impl Ord for HandKind {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.to_u8().cmp(&other.to_u8())
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TieBreak {
+    // the cards are compared in the order they were dealt
+    InOrder,
+    // the cards are compared from the largest group down, the higher card first within a size
+    Grouped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RuleSet {
+    // the cards from the weakest to the strongest
+    order: Vec<char>,
+    // the cards that join the largest group of the hand
+    wild: Vec<char>,
+    size: usize,
+    tie_break: TieBreak,
+}
+
+impl RuleSet {
+    fn standard() -> Self {
+        RuleSet {
+            order: "23456789TJQKA".chars().collect(),
+            wild: vec![],
+            size: 5,
+            tie_break: TieBreak::InOrder,
+        }
+    }
+
+    // `J` is a joker, wild and the weakest card
+    fn jokers() -> Self {
+        RuleSet {
+            order: "J23456789TQKA".chars().collect(),
+            wild: vec!['J'],
+            ..RuleSet::standard()
+        }
+    }
+
+    fn rank(&self, c: char) -> Result<u8> {
+        match self.order.iter().position(|&o| o == c) {
+            Some(rank) => Ok(rank as u8),
+            None => err!("Wrong hand char: {:?}", c),
+        }
+    }
+}
+
+impl FromStr for RuleSet {
+    type Err = Box<dyn Error>;
+
+    // space separated `order=<cards>`, `wild=<cards>`, `size=<n>` and `tie=order|grouped`,
+    // a missing one keeps the standard rule
+    fn from_str(s: &str) -> Result<Self> {
+        let mut rules = RuleSet::standard();
+        for rule in s.split_whitespace() {
+            match rule.split_once('=') {
+                Some(("order", cards)) => rules.order = cards.chars().collect(),
+                Some(("wild", cards)) => rules.wild = cards.chars().collect(),
+                Some(("size", size)) => rules.size = size.parse()?,
+                Some(("tie", "order")) => rules.tie_break = TieBreak::InOrder,
+                Some(("tie", "grouped")) => rules.tie_break = TieBreak::Grouped,
+                _ => {
+                    return err!(
+                        "Wrong rule: {:?}, expect order=<cards>, wild=<cards>, size=<n> \
+                         or tie=order|grouped",
+                        rule
+                    )
+                }
+            }
+        }
+        let mut cards = rules.order.clone();
+        cards.sort();
+        cards.dedup();
+        if cards.len() != rules.order.len() {
+            return err!("Repeated card in the order: {:?}", rules.order);
+        }
+        for c in &rules.wild {
+            rules.rank(*c)?;
+        }
+        Ok(rules)
+    }
+}
+
+impl fmt::Display for RuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cards = |cs: &[char]| cs.iter().collect::<String>();
+        let tie = match self.tie_break {
+            TieBreak::InOrder => "order",
+            TieBreak::Grouped => "grouped",
+        };
+        write!(
+            f,
+            "order={} wild={} size={} tie={tie}",
+            cards(&self.order),
+            cards(&self.wild),
+            self.size
+        )
     }
 }
 
 #[derive(Debug, Clone)]
 struct Hand {
+    cards: Vec<char>,
     kind: HandKind,
-    raw: [i8; 5],
+    // the ranks of the cards in the order they were dealt
+    raw: Vec<u8>,
+    // the positions of the cards in the order the tie-break compares them
+    tie: Vec<usize>,
     bid: usize,
+    // the card the wild cards stand for, None without wild cards
+    wild_as: Option<char>,
 }
 
 impl Hand {
-    fn from_str(s: &str, joker_rule: bool) -> Result<Self> {
+    fn from_str(s: &str, rules: &RuleSet) -> Result<Self> {
         if let Some((hand_str, bid_str)) = s.split_once(' ') {
-            let mut raw: [i8; 5] = [0; 5];
-            if hand_str.len() != 5 {
-                return err!("Wrong hand length: {:?}", hand_str);
-            }
-            let mut count = HashMap::with_capacity(5);
-            for (i, c) in hand_str.chars().enumerate() {
-                raw[i] = match c {
-                    '2' => 0,
-                    '3' => 1,
-                    '4' => 2,
-                    '5' => 3,
-                    '6' => 4,
-                    '7' => 5,
-                    '8' => 6,
-                    '9' => 7,
-                    'T' => 8,
-                    'J' => {
-                        if joker_rule {
-                            -1
-                        } else {
-                            9
-                        }
-                    }
-                    'Q' => 10,
-                    'K' => 11,
-                    'A' => 12,
-                    _ => return err!("Wrong hand char: {:?}", c),
-                };
-                *count.entry(raw[i]).or_insert(0) += 1;
-            }
-            let kind = HandKind::new(count)?;
-            let bid = bid_str.parse()?;
-            return Ok(Self { kind, raw, bid });
+            return Hand::new(hand_str, bid_str.parse()?, rules);
         }
         err!("Wrong input for a hand: {:?}", s)
     }
+
+    fn new(hand_str: &str, bid: usize, rules: &RuleSet) -> Result<Self> {
+        let cards: Vec<_> = hand_str.chars().collect();
+        if cards.len() != rules.size {
+            return err!("Wrong hand length: {:?}", hand_str);
+        }
+        let raw = cards
+            .iter()
+            .map(|&c| rules.rank(c))
+            .collect::<Result<Vec<_>>>()?;
+        let mut count = HashMap::with_capacity(rules.size);
+        let mut wild = 0;
+        for (&c, &r) in cards.iter().zip(&raw) {
+            if rules.wild.contains(&c) {
+                wild += 1;
+            } else {
+                *count.entry((r, c)).or_insert(0) += 1;
+            }
+        }
+        // the wild cards join the largest group, the strongest one of a tie
+        let wild_as = (wild > 0).then(|| {
+            count
+                .iter()
+                .max_by_key(|((r, _), n)| (**n, *r))
+                .map_or(*rules.order.last().unwrap(), |((_, c), _)| *c)
+        });
+        let kind = HandKind::new(count.into_values().collect(), wild);
+        let mut tie: Vec<_> = (0..cards.len()).collect();
+        if rules.tie_break == TieBreak::Grouped {
+            let size = |r: u8| raw.iter().filter(|&&o| o == r).count();
+            tie.sort_by_key(|&i| Reverse((size(raw[i]), raw[i])));
+        }
+        Ok(Self {
+            cards,
+            kind,
+            raw,
+            tie,
+            bid,
+            wild_as,
+        })
+    }
+}
+
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.cards.iter().collect::<String>())
+    }
 }
 
 impl PartialEq for Hand {
@@ -128,37 +270,124 @@ impl PartialOrd for Hand {
 
 impl Ord for Hand {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match self.kind.cmp(&other.kind) {
-            Equal => {
-                for (a, b) in self.raw.iter().zip(other.raw.iter()) {
-                    match a.cmp(b) {
-                        Less => return Less,
-                        Equal => continue,
-                        Greater => return Greater,
-                    }
+        compare(self, other).ordering
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decider {
+    Kind,
+    // the n-th card the tie-break looks at, from 0
+    Card(usize),
+    // same kind and same cards
+    Nothing,
+}
+
+// how two hands are ordered and the rule that ordered them
+#[derive(Debug, Clone, Copy)]
+struct Comparison<'a> {
+    a: &'a Hand,
+    b: &'a Hand,
+    ordering: std::cmp::Ordering,
+    decider: Decider,
+}
+
+fn compare<'a>(a: &'a Hand, b: &'a Hand) -> Comparison<'a> {
+    let (ordering, decider) = match a.kind.cmp(&b.kind) {
+        Equal => {
+            let ranks = |h: &'a Hand| h.tie.iter().map(|&i| h.raw[i]);
+            match ranks(a).zip(ranks(b)).position(|(x, y)| x != y) {
+                Some(n) => {
+                    let (x, y) = (a.raw[a.tie[n]], b.raw[b.tie[n]]);
+                    (x.cmp(&y), Decider::Card(n))
                 }
-                Equal
+                None => (Equal, Decider::Nothing),
             }
-            ord => ord,
         }
+        ord => (ord, Decider::Kind),
+    };
+    Comparison {
+        a,
+        b,
+        ordering,
+        decider,
     }
 }
 
-fn parse_input(input: &str, joker_rule: bool) -> Vec<Hand> {
+impl fmt::Display for Comparison<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (a, b) = (self.a, self.b);
+        let sign = match self.ordering {
+            Less => "<",
+            Equal => "=",
+            Greater => ">",
+        };
+        write!(f, "{a} {sign} {b}: ")?;
+        match self.decider {
+            Decider::Kind => write!(f, "{} against {}", a.kind, b.kind),
+            Decider::Card(n) => {
+                let (x, y) = (a.cards[a.tie[n]], b.cards[b.tie[n]]);
+                if a.tie[n] == n && b.tie[n] == n {
+                    write!(f, "both {}, card {} is {x} against {y}", a.kind, n + 1)
+                } else {
+                    write!(
+                        f,
+                        "both {}, grouped card {} is {x} against {y}",
+                        a.kind,
+                        n + 1
+                    )
+                }
+            }
+            Decider::Nothing => write!(f, "both {} with cards of the same rank", a.kind),
+        }
+    }
+}
+
+fn parse_input(input: &str, rules: &RuleSet) -> Result<Vec<Hand>> {
     input
         .lines()
-        .map(|l| Hand::from_str(l.trim(), joker_rule).unwrap())
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| Hand::from_str(l.trim(), rules))
         .collect()
 }
 
-fn part1(input: &str) -> Result<usize> {
-    let start = Instant::now();
+fn winnings(input: &str, rules: &RuleSet) -> Result<usize> {
+    let mut hands = parse_input(input, rules)?;
 
-    let mut hands = parse_input(input, false);
+    hands.sort();
+
+    Ok(hands.iter().enumerate().map(|(i, h)| (i + 1) * h.bid).sum())
+}
+
+// every hand from the weakest up with its rank and winnings
+fn report(input: &str, rules: &RuleSet) -> Result<Vec<String>> {
+    let mut hands = parse_input(input, rules)?;
 
     hands.sort();
 
-    let result = hands.iter().enumerate().map(|(i, h)| (i + 1) * h.bid).sum();
+    Ok(hands
+        .iter()
+        .enumerate()
+        .map(|(i, h)| {
+            let wild = h
+                .wild_as
+                .map(|c| format!(" wild as {c}"))
+                .unwrap_or_default();
+            format!(
+                "rank {} {h} {} bid {} winnings {}{wild}",
+                i + 1,
+                h.kind,
+                h.bid,
+                (i + 1) * h.bid
+            )
+        })
+        .collect())
+}
+
+fn part1(input: &str) -> Result<usize> {
+    let start = Instant::now();
+
+    let result = winnings(input, &RuleSet::standard())?;
 
     writeln!(io::stdout(), "Part 1: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
@@ -168,11 +397,7 @@ fn part1(input: &str) -> Result<usize> {
 fn part2(input: &str) -> Result<usize> {
     let start = Instant::now();
 
-    let mut hands = parse_input(input, true);
-
-    hands.sort();
-
-    let result = hands.iter().enumerate().map(|(i, h)| (i + 1) * h.bid).sum();
+    let result = winnings(input, &RuleSet::jokers())?;
 
     writeln!(io::stdout(), "Part 2: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
@@ -183,6 +408,28 @@ fn main() -> Result<()> {
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
+    let options = Options::from_args()?;
+    if !options.rules.is_empty() || options.report || !options.compare.is_empty() {
+        let rule_sets = if options.rules.is_empty() {
+            vec![RuleSet::standard(), RuleSet::jokers()]
+        } else {
+            options.rules
+        };
+        for rules in &rule_sets {
+            writeln!(io::stdout(), "{rules}: {}", winnings(&input, rules)?)?;
+            if options.report {
+                for line in report(&input, rules)? {
+                    writeln!(io::stdout(), "  {line}")?;
+                }
+            }
+            for (a, b) in &options.compare {
+                let (a, b) = (Hand::new(a, 0, rules)?, Hand::new(b, 0, rules)?);
+                writeln!(io::stdout(), "  {}", compare(&a, &b))?;
+            }
+        }
+        return Ok(());
+    }
+
     part1(&input)?;
     part2(&input)?;
     Ok(())
@@ -196,8 +443,159 @@ KK677 28
 KTJJT 220
 QQQJA 483";
 
-    assert_eq!(part1(&input).unwrap(), 6440);
-    assert_eq!(part2(&input).unwrap(), 5905);
+    assert_eq!(part1(input).unwrap(), 6440);
+    assert_eq!(part2(input).unwrap(), 5905);
+}
+
+#[test]
+fn house_rules() {
+    let input = "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483";
+
+    let jokers: RuleSet = "order=J23456789TQKA wild=J".parse().unwrap();
+    assert_eq!(jokers, RuleSet::jokers());
+    assert_eq!(jokers.to_string().parse::<RuleSet>().unwrap(), jokers);
+    assert_eq!(winnings(input, &jokers).unwrap(), 5905);
+
+    // both 3 and J are wild, KTJJT and QQQJA become four of a kind
+    let rules: RuleSet = "wild=3J".parse().unwrap();
+    let hands = parse_input(input, &rules).unwrap();
+    let kinds: Vec<_> = hands.iter().map(|h| h.kind.to_string()).collect();
+    assert_eq!(
+        kinds,
+        [
+            "three of a kind",
+            "four of a kind",
+            "two pair",
+            "four of a kind",
+            "four of a kind"
+        ]
+    );
+    // between equal kinds the first card decides, wild or not
+    assert!(hands[3] > hands[4] && hands[4] > hands[1]);
+
+    // poker-style ties compare the larger group first
+    let rules: RuleSet = "tie=grouped".parse().unwrap();
+    let low_pair = Hand::from_str("A2233 1", &rules).unwrap();
+    let high_pair = Hand::from_str("23344 1", &rules).unwrap();
+    assert_eq!(low_pair.tie, [3, 4, 1, 2, 0]);
+    assert!(high_pair > low_pair);
+    assert!(Hand::from_str("A2233 1", &RuleSet::standard()).unwrap() > high_pair);
+
+    let rules: RuleSet = "size=3".parse().unwrap();
+    assert!(Hand::from_str("AAAA 1", &rules).is_err());
+    assert!(Hand::from_str("AAK 1", &rules).unwrap() > Hand::from_str("AKQ 1", &rules).unwrap());
+    assert!("order=AA".parse::<RuleSet>().is_err());
+    assert!("wild=X".parse::<RuleSet>().is_err());
+    assert!("tie=random".parse::<RuleSet>().is_err());
+}
+
+#[test]
+fn explanations() {
+    let input = "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483";
+
+    assert_eq!(
+        report(input, &RuleSet::jokers()).unwrap(),
+        [
+            "rank 1 32T3K one pair bid 765 winnings 765",
+            "rank 2 KK677 two pair bid 28 winnings 56",
+            "rank 3 T55J5 four of a kind bid 684 winnings 2052 wild as 5",
+            "rank 4 QQQJA four of a kind bid 483 winnings 1932 wild as Q",
+            "rank 5 KTJJT four of a kind bid 220 winnings 1100 wild as T",
+        ]
+    );
+
+    let explain = |a: &str, b: &str, rules: &RuleSet| {
+        let (a, b) = (
+            Hand::new(a, 0, rules).unwrap(),
+            Hand::new(b, 0, rules).unwrap(),
+        );
+        let comparison = compare(&a, &b);
+        assert_eq!(comparison.ordering, a.cmp(&b));
+        comparison.to_string()
+    };
+    let jokers = RuleSet::jokers();
+    assert_eq!(
+        explain("JKKK2", "QQQQ2", &jokers),
+        "JKKK2 < QQQQ2: both four of a kind, card 1 is J against Q"
+    );
+    assert_eq!(
+        explain("JJJJJ", "AAAAK", &jokers),
+        "JJJJJ > AAAAK: five of a kind against four of a kind"
+    );
+    assert_eq!(
+        explain("T55J5", "T5J55", &jokers),
+        "T55J5 > T5J55: both four of a kind, card 3 is 5 against J"
+    );
+    assert_eq!(
+        explain("KK677", "KK677", &RuleSet::standard()),
+        "KK677 = KK677: both two pair with cards of the same rank"
+    );
+    assert_eq!(
+        explain("A2233", "23344", &"tie=grouped".parse().unwrap()),
+        "A2233 < 23344: both two pair, grouped card 1 is 3 against 4"
+    );
+    assert_eq!(Hand::new("JJJJJ", 0, &jokers).unwrap().wild_as, Some('A'));
+}
+
+#[test]
+fn wild_cards_by_substitution() {
+    // the best kind over every assignment of the wild cards to a card of the order
+    fn brute(hand: &mut [char], wild_at: &[usize], order: &[char]) -> HandKind {
+        match wild_at.split_first() {
+            Some((&i, rest)) => order
+                .iter()
+                .map(|&c| {
+                    hand[i] = c;
+                    brute(hand, rest, order)
+                })
+                .max()
+                .unwrap(),
+            None => {
+                let mut count = HashMap::new();
+                for c in hand.iter() {
+                    *count.entry(c).or_insert(0) += 1;
+                }
+                HandKind::new(count.into_values().collect(), 0)
+            }
+        }
+    }
+
+    // two kinds of wild card among three plain ones, and one among two for longer hands
+    for (rules, sizes) in [("order=ABCVW wild=VW", 1..=5), ("order=ABW wild=W", 6..=7)] {
+        let rules: RuleSet = rules.parse().unwrap();
+        for size in sizes {
+            let rules = RuleSet {
+                size,
+                ..rules.clone()
+            };
+            let mut hand = vec![0; size];
+            loop {
+                let cards: String = hand.iter().map(|&i| rules.order[i]).collect();
+                let kind = Hand::from_str(&format!("{cards} 1"), &rules).unwrap().kind;
+                let mut chars: Vec<_> = cards.chars().collect();
+                let wild_at: Vec<_> = (0..size)
+                    .filter(|&i| rules.wild.contains(&chars[i]))
+                    .collect();
+                assert_eq!(kind, brute(&mut chars, &wild_at, &rules.order), "{cards}");
+                assert_eq!(kind.groups.iter().sum::<usize>(), size, "{cards}");
+
+                // the next hand in base `order.len()`
+                let Some(i) = hand.iter().position(|&i| i + 1 < rules.order.len()) else {
+                    break;
+                };
+                hand[i] += 1;
+                hand[..i].fill(0);
+            }
+        }
+    }
 }
 
 #[test]
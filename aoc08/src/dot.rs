@@ -0,0 +1,267 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt::Write;
+
+use crate::{next_node, Hits, Network, NodeMap};
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+const COLOURS: [&str; 6] = ["red", "blue", "darkgreen", "orange", "purple", "brown"];
+
+// the name of every node id, the reverse of the `NodeMap`
+fn node_names(map: &NodeMap) -> Vec<Option<&str>> {
+    let mut names = vec![None; map.values().max().map_or(0, |id| id + 1)];
+    for (name, &id) in map {
+        names[id] = Some(name.as_str());
+    }
+    names
+}
+
+// the name of a node, or its id for a node without one
+fn node_name(names: &[Option<&str>], id: usize) -> String {
+    match names.get(id) {
+        Some(Some(name)) => name.to_string(),
+        _ => id.to_string(),
+    }
+}
+
+// what a walker does within its prefix and cycle
+struct Ghost {
+    // the node at every step until the cycle repeats
+    path: Vec<usize>,
+    cycle_start: usize,
+    ends: BTreeSet<usize>,
+}
+
+impl Ghost {
+    fn new(start: usize, hits: &Hits, instrs: &[char], network: &Network) -> Result<Self> {
+        let mut state = (start, 0);
+        let mut path = vec![start];
+        for _ in 0..hits.cycle_start + hits.period {
+            state = next_node(state, instrs, network)?;
+            path.push(state.0);
+        }
+        let ends = hits.prefix.iter().chain(&hits.cycle).map(|&s| path[s]);
+        Ok(Ghost {
+            ends: ends.collect(),
+            path,
+            cycle_start: hits.cycle_start,
+        })
+    }
+
+    // the (from, to) of every step taken
+    fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.path.windows(2).map(|w| (w[0], w[1]))
+    }
+}
+
+// a line of the legend, e.g. `11A: cycle of 2 steps from step 1, ends at 2 + 2k`
+fn describe(name: &str, hits: &Hits) -> String {
+    let mut ends: Vec<_> = hits.prefix.iter().map(|s| s.to_string()).collect();
+    ends.extend(hits.cycle.iter().map(|s| format!("{s} + {}k", hits.period)));
+    let ends = if ends.is_empty() {
+        "never ends".to_string()
+    } else {
+        format!("ends at {}", ends.join(", "))
+    };
+    // the lcm of the first ends is only the answer when a walker ends at exactly the
+    // multiples of its first end
+    let all: Vec<_> = hits.prefix.iter().chain(&hits.cycle).copied().collect();
+    let multiples = |f: usize| {
+        let until = hits.cycle_start + hits.period;
+        f > 0 && hits.period.is_multiple_of(f) && all.iter().copied().eq((f..until).step_by(f))
+    };
+    let lcm = if all.first().is_some_and(|&f| multiples(f)) {
+        "lcm holds"
+    } else {
+        "lcm does not hold"
+    };
+    format!(
+        "{name}: cycle of {} steps from step {}, {ends}, {lcm}",
+        hits.period, hits.cycle_start
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", escape(s))
+}
+
+// the network as a Graphviz digraph with L/R edges, every walker's path in its own colour, its
+// cycle in bold and the end nodes it reaches doubled; runs of at least `collapse` nodes, each
+// the single successor of the one before it and its single predecessor, are drawn as one node
+// see: https://graphviz.org/doc/info/lang.html
+pub fn to_dot(
+    instrs: &[char],
+    network: &Network,
+    map: &NodeMap,
+    walkers: &[(&str, Hits)],
+    collapse: Option<usize>,
+) -> Result<String> {
+    let names = node_names(map);
+    let ids: Vec<_> = (0..names.len()).filter(|&id| names[id].is_some()).collect();
+
+    let mut ghosts = vec![];
+    for (name, hits) in walkers {
+        ghosts.push(Ghost::new(map[*name], hits, instrs, network)?);
+    }
+    let starts: BTreeSet<_> = ghosts.iter().map(|g| g.path[0]).collect();
+    let ends: BTreeSet<_> = ghosts.iter().flat_map(|g| g.ends.iter().copied()).collect();
+    // the first walker whose path and cycle goes through a node
+    let mut on_path = BTreeMap::new();
+    let mut on_cycle = BTreeMap::new();
+    for (i, g) in ghosts.iter().enumerate().rev() {
+        for (s, &node) in g.path.iter().enumerate() {
+            on_path.insert(node, i);
+            if s >= g.cycle_start {
+                on_cycle.insert(node, i);
+            }
+        }
+    }
+    // the walkers that take each (from, to) edge and with which instructions
+    let mut edges: BTreeMap<(usize, usize), (BTreeSet<char>, BTreeSet<usize>)> = BTreeMap::new();
+    for &id in &ids {
+        let (l, r) = network[id];
+        edges.entry((id, l)).or_default().0.insert('L');
+        edges.entry((id, r)).or_default().0.insert('R');
+    }
+    for (i, g) in ghosts.iter().enumerate() {
+        for (from, to) in g.edges() {
+            edges.entry((from, to)).or_default().1.insert(i);
+        }
+    }
+
+    // the chain every collapsed node belongs to
+    let mut chain_of = BTreeMap::new();
+    let mut chains: Vec<Vec<usize>> = vec![];
+    if let Some(min_len) = collapse {
+        let mut preds: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        let mut succs: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for &(from, to) in edges.keys() {
+            preds.entry(to).or_default().insert(from);
+            succs.entry(from).or_default().insert(to);
+        }
+        let only = |set: Option<&BTreeSet<usize>>| match set {
+            Some(set) if set.len() == 1 => set.first().copied(),
+            _ => None,
+        };
+        // the starts and ends stay on their own
+        let shown = |id: usize| starts.contains(&id) || ends.contains(&id);
+        // the node `id` merges into, when it is all that `id` leads to and all that leads there
+        let merges = |id: usize| {
+            let next = only(succs.get(&id))?;
+            let merge =
+                next != id && !shown(id) && !shown(next) && only(preds.get(&next)) == Some(id);
+            merge.then_some(next)
+        };
+        for &id in &ids {
+            // a run starts at a node no other node merges into
+            let pred = only(preds.get(&id));
+            if shown(id) || pred.is_some_and(|p| merges(p) == Some(id)) {
+                continue;
+            }
+            let mut chain = vec![id];
+            while let Some(next) = merges(chain[chain.len() - 1]) {
+                if chain.contains(&next) {
+                    break;
+                }
+                chain.push(next);
+            }
+            if chain.len() >= min_len.max(2) {
+                for &c in &chain {
+                    chain_of.insert(c, chains.len());
+                }
+                chains.push(chain);
+            }
+        }
+    }
+    let node_id = |id: usize| match chain_of.get(&id) {
+        Some(c) => quote(&format!("chain {c}")),
+        None => quote(&node_name(&names, id)),
+    };
+    let style = |id: usize| {
+        let mut attrs = vec![];
+        // a start that is also an end is a doubled box
+        match (starts.contains(&id), ends.contains(&id)) {
+            (true, true) => attrs.extend(["shape=box".to_string(), "peripheries=2".to_string()]),
+            (true, false) => attrs.push("shape=box".to_string()),
+            (false, true) => attrs.push("shape=doublecircle".to_string()),
+            (false, false) => {}
+        }
+        if let Some(&i) = on_path.get(&id) {
+            attrs.push(format!("color={}", COLOURS[i % COLOURS.len()]));
+        }
+        if on_cycle.contains_key(&id) {
+            attrs.push("style=bold".to_string());
+        }
+        attrs
+    };
+
+    let mut dot = String::new();
+    writeln!(dot, "digraph desert {{")?;
+    if !walkers.is_empty() {
+        // `\l` ends a left aligned line
+        let legend: Vec<_> = walkers
+            .iter()
+            .map(|(n, h)| escape(&describe(n, h)))
+            .collect();
+        writeln!(dot, "  label=\"{}\\l\";", legend.join("\\l"))?;
+        writeln!(dot, "  labelloc=b;")?;
+    }
+    for &id in ids.iter().filter(|id| !chain_of.contains_key(id)) {
+        let attrs = style(id);
+        if attrs.is_empty() {
+            writeln!(dot, "  {};", node_id(id))?;
+        } else {
+            writeln!(dot, "  {} [{}];", node_id(id), attrs.join(", "))?;
+        }
+    }
+    for chain in &chains {
+        let (first, last) = (chain[0], chain[chain.len() - 1]);
+        let label = format!(
+            "\"{} … {}\\n{} nodes\"",
+            escape(&node_name(&names, first)),
+            escape(&node_name(&names, last)),
+            chain.len()
+        );
+        let mut attrs = vec![format!("label={label}"), "shape=note".into()];
+        if let Some(i) = chain.iter().find_map(|c| on_path.get(c)) {
+            attrs.push(format!("color={}", COLOURS[i % COLOURS.len()]));
+        }
+        if chain.iter().any(|c| on_cycle.contains_key(c)) {
+            attrs.push("style=bold".into());
+        }
+        writeln!(dot, "  {} [{}];", node_id(first), attrs.join(", "))?;
+    }
+    for (&(from, to), (dirs, walkers)) in &edges {
+        if chain_of.contains_key(&from) && chain_of.get(&from) == chain_of.get(&to) {
+            continue;
+        }
+        let dirs: String = dirs
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+        let mut attrs = vec![format!("label={dirs}")];
+        if !walkers.is_empty() {
+            let colours: Vec<_> = walkers
+                .iter()
+                .map(|&i| COLOURS[i % COLOURS.len()])
+                .collect();
+            attrs.push(format!("color={}", quote(&colours.join(":"))));
+            attrs.push("penwidth=2".into());
+        }
+        writeln!(
+            dot,
+            "  {} -> {} [{}];",
+            node_id(from),
+            node_id(to),
+            attrs.join(", ")
+        )?;
+    }
+    writeln!(dot, "}}")?;
+    Ok(dot)
+}
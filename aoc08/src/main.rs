@@ -1,15 +1,72 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::error::Error;
 use std::io::{self, Read, Write};
 use std::time::Instant;
 
-#[allow(unused_macros)]
+use pattern::Pattern;
+
+mod dot;
+mod pattern;
+#[cfg(test)]
+#[path = "../../common/xorshift.rs"]
+mod xorshift;
+
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
 }
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
+#[derive(Debug, Default)]
+struct Options {
+    queries: Vec<(Pattern, Pattern)>,
+    dot: Option<(Pattern, Pattern)>,
+    collapse: Option<usize>,
+}
+
+impl Options {
+    // `--query <starts> <ends>` prints the steps from every start to its first end, and the
+    // first step any and all of the walkers are on an end at once,
+    // `--dot <starts> <ends>` prints the network as Graphviz DOT with the walkers highlighted,
+    // `--collapse <n>` draws runs of at least n nodes with a single way in and out as one
+    fn from_args() -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing a value of {arg}"));
+            match arg.as_str() {
+                "--query" => {
+                    let starts = value()?.parse()?;
+                    options.queries.push((starts, value()?.parse()?));
+                }
+                "--dot" => {
+                    let starts = value()?.parse()?;
+                    options.dot = Some((starts, value()?.parse()?));
+                }
+                "--collapse" => options.collapse = Some(value()?.parse()?),
+                _ => {
+                    return err!(
+                        "unknown argument {arg:?}, expect --query <starts> <ends>, \
+                         --dot <starts> <ends> or --collapse <n>"
+                    )
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+// the integer type of the hot arithmetic, `--features wide` switches it to 128 bits
+#[cfg(not(feature = "wide"))]
+type Int = usize;
+#[cfg(feature = "wide")]
+type Int = u128;
+
+#[path = "../../common/checked.rs"]
+mod checked;
+
+use checked::{add, mul};
+
 type Network = Vec<(usize, usize)>;
 type NodeMap = HashMap<String, usize>;
 
@@ -50,7 +107,6 @@ fn parse_input(input: &str) -> Result<(Vec<char>, Network, NodeMap)> {
             return err!("Unable to parse node: {:?}", line);
         }
     }
-    dbg!(network.len());
     Ok((instrs, network, map))
 }
 
@@ -62,8 +118,9 @@ fn next_node(start: (usize, usize), instrs: &[char], network: &Network) -> Resul
     })
 }
 
+// the length of the path before the cycle and the length of the cycle
 // see: https://en.wikipedia.org/wiki/Cycle_detection#Floyd's_tortoise_and_hare
-fn cycle_detect(start: usize, instrs: &[char], network: &Network) -> Result<usize> {
+fn cycle_detect(start: usize, instrs: &[char], network: &Network) -> Result<(usize, usize)> {
     let mut tortoise = next_node((start, 0), instrs, network)?;
     let mut hare = next_node(tortoise, instrs, network)?;
     while tortoise != hare {
@@ -91,43 +148,95 @@ fn cycle_detect(start: usize, instrs: &[char], network: &Network) -> Result<usiz
         lam += 1;
     }
 
-    Ok(mu + lam)
+    Ok((mu, lam))
+}
+
+// the steps at which a walker is on an end node: every step in `prefix`, and
+// `hit + k * period` for every hit in `cycle` and every k >= 0
+#[derive(Debug, Clone, PartialEq)]
+struct Hits {
+    prefix: Vec<usize>,
+    // the first step in the cycle
+    cycle_start: usize,
+    period: usize,
+    cycle: Vec<usize>,
+}
+
+impl Hits {
+    fn first(&self) -> Option<usize> {
+        self.prefix.first().or(self.cycle.first()).copied()
+    }
 }
 
 // search until cycle
-fn get_steps(
-    start: usize,
-    end_ids: &[usize],
-    instrs: &[char],
-    network: &Network,
-) -> Result<Vec<(usize, usize)>> {
+fn get_steps(start: usize, end_ids: &[usize], instrs: &[char], network: &Network) -> Result<Hits> {
     let mut cur = start;
     let mut cur_instr = 0;
 
-    let mut ends = vec![];
-
-    let limit = cycle_detect(start, instrs, network)?;
+    let (mu, lam) = cycle_detect(start, instrs, network)?;
+    let mut hits = Hits {
+        prefix: vec![],
+        cycle_start: mu,
+        period: lam,
+        cycle: vec![],
+    };
 
-    for steps in 0..limit {
+    for steps in 0..mu + lam {
         if end_ids.binary_search(&cur).is_ok() {
-            ends.push((cur, steps));
+            if steps < mu {
+                hits.prefix.push(steps);
+            } else {
+                hits.cycle.push(steps);
+            }
         }
         (cur, cur_instr) = next_node((cur, cur_instr), instrs, network)?;
     }
-    Ok(ends)
+    Ok(hits)
+}
+
+// the walker from every node whose name matches `starts`, sorted by name, with the steps at
+// which it is on a node whose name matches `ends`
+fn walkers<'a>(
+    starts: &Pattern,
+    ends: &Pattern,
+    instrs: &[char],
+    network: &Network,
+    map: &'a NodeMap,
+) -> Result<Vec<(&'a str, Hits)>> {
+    let mut start_ids: Vec<_> = map
+        .iter()
+        .filter(|(k, _)| starts.matches(k))
+        .map(|(k, v)| (k.as_str(), *v))
+        .collect();
+    start_ids.sort();
+    let mut end_ids: Vec<_> = map
+        .iter()
+        .filter(|(k, _)| ends.matches(k))
+        .map(|(_, v)| *v)
+        .collect();
+    end_ids.sort();
+    start_ids
+        .into_iter()
+        .map(|(name, start)| Ok((name, get_steps(start, &end_ids, instrs, network)?)))
+        .collect()
+}
+
+// the first step at which any walker is on an end node
+fn first_hit(walkers: &[Hits]) -> Option<usize> {
+    walkers.iter().filter_map(|h| h.first()).min()
 }
 
 fn part1(instrs: &[char], network: &Network, map: &NodeMap) -> Result<usize> {
     let start = Instant::now();
 
-    let &start_id = map
-        .get("AAA")
-        .expect("Unable to find the AAA node in network");
-    let &end_id = map
-        .get("ZZZ")
-        .expect("Unable to find the ZZZ node in network");
-
-    let steps = get_steps(start_id, &[end_id], instrs, network)?[0].1;
+    let (starts, ends) = (Pattern::Exact("AAA".into()), Pattern::Exact("ZZZ".into()));
+    let walkers = walkers(&starts, &ends, instrs, network, map)?;
+    let Some((_, hits)) = walkers.first() else {
+        return err!("Unable to find the AAA node in network");
+    };
+    let Some(steps) = hits.first() else {
+        return err!("Unable to reach the ZZZ node from the AAA node");
+    };
 
     writeln!(io::stdout(), "Part 1: {steps}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
@@ -135,7 +244,7 @@ fn part1(instrs: &[char], network: &Network, map: &NodeMap) -> Result<usize> {
 }
 
 // https://zh.wikipedia.org/wiki/%E6%9C%80%E5%A4%A7%E5%85%AC%E5%9B%A0%E6%95%B8
-fn gcd(a: usize, b: usize) -> usize {
+fn gcd(a: Int, b: Int) -> Int {
     let (a, b) = (a.max(b), b.min(a));
     if b == 0 {
         a
@@ -145,74 +254,147 @@ fn gcd(a: usize, b: usize) -> usize {
 }
 
 // https://zh.wikipedia.org/zh-hans/%E6%9C%80%E5%B0%8F%E5%85%AC%E5%80%8D%E6%95%B8
-fn lcm(a: usize, b: usize) -> usize {
-    a * b / gcd(a, b)
+fn lcm(a: Int, b: Int) -> Result<Int> {
+    mul(a / gcd(a, b), b)
 }
 
-fn dfs(
-    start_to_end_steps: &HashMap<usize, Vec<(usize, usize)>>,
-    start_count: usize,
-    end_count: usize,
-    visited_start: &mut HashSet<usize>,
-    visited_end: &mut HashSet<usize>,
-) -> Option<usize> {
-    let mut min_steps = usize::MAX;
-    for (&start, end_steps) in start_to_end_steps.iter() {
-        if visited_start.insert(start) {
-            for &(end, steps) in end_steps {
-                if visited_end.insert(end) {
-                    if let Some(remain_steps) = dfs(
-                        start_to_end_steps,
-                        start_count,
-                        end_count,
-                        visited_start,
-                        visited_end,
-                    ) {
-                        min_steps = min_steps.min(lcm(steps, remain_steps));
-                    }
-                    visited_end.remove(&end);
-                }
-            }
-            visited_start.remove(&start);
+// (a + b) % m for a and b below m, without overflow
+fn add_mod(a: Int, b: Int, m: Int) -> Int {
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
+
+// (a * b) % m for a and b below m, without overflow
+fn mul_mod(mut a: Int, mut b: Int, m: Int) -> Int {
+    let mut product = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            product = add_mod(product, a, m);
         }
+        a = add_mod(a, a, m);
+        b >>= 1;
     }
-    if min_steps != usize::MAX {
-        Some(min_steps)
-    } else if visited_start.len() == start_count && visited_end.len() == end_count {
-        Some(1)
+    product
+}
+
+// the x below m with a * x % m == 1, for a coprime with m
+// see: https://en.wikipedia.org/wiki/Extended_Euclidean_algorithm#Modular_integers
+fn inverse(a: Int, m: Int) -> Int {
+    // the coefficients are kept modulo m so they never go negative
+    let (mut old_r, mut r) = (a % m, m);
+    let (mut old_s, mut s) = (1 % m, 0);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, add_mod(old_s, m - mul_mod(q % m, s, m), m));
+    }
+    old_s
+}
+
+// the x with x % m1 == a1 and x % m2 == a2 as (x % lcm, lcm), the moduli need not be coprime
+// see: https://en.wikipedia.org/wiki/Chinese_remainder_theorem#Generalization_to_non-coprime_moduli
+fn crt((a1, m1): (Int, Int), (a2, m2): (Int, Int)) -> Result<Option<(Int, Int)>> {
+    let g = gcd(m1, m2);
+    let diff = if a2 >= a1 {
+        a2 - a1
     } else {
-        None
+        m2 - (a1 - a2) % m2
+    };
+    if diff % g != 0 {
+        return Ok(None);
     }
+    let l = lcm(m1, m2)?;
+    // a1 + m1 * k with k = diff / g * (m1 / g)^-1 modulo m2 / g
+    let m = m2 / g;
+    let k = mul_mod((diff / g) % m, inverse((m1 / g) % m, m), m);
+    Ok(Some((a1 + m1 * k, l)))
 }
 
-fn part2(instrs: &[char], network: &Network, map: &NodeMap) -> Result<usize> {
-    let start = Instant::now();
+// the steps, as residues of one modulus, at which the walkers of `solutions` and `hits` are
+// all on an end node in their cycles; a residue only pairs with the hits it agrees with modulo
+// the gcd of the periods, so no pair that has no solution is ever tried
+fn meet(solutions: &[(Int, Int)], hits: &Hits) -> Result<Vec<(Int, Int)>> {
+    let Some(&(_, m)) = solutions.first() else {
+        return Ok(vec![]);
+    };
+    let period = hits.period as Int;
+    let g = gcd(m, period);
+    let mut by_residue: HashMap<Int, Vec<Int>> = HashMap::new();
+    for &hit in &hits.cycle {
+        let hit = hit as Int % period;
+        by_residue.entry(hit % g).or_default().push(hit);
+    }
+    let mut next = vec![];
+    for &(x, m) in solutions {
+        for &hit in by_residue.get(&(x % g)).into_iter().flatten() {
+            next.extend(crt((x, m), (hit, period))?);
+        }
+    }
+    next.sort();
+    next.dedup();
+    Ok(next)
+}
 
-    let start_ids: Vec<_> = map
-        .iter()
-        .filter(|(k, _)| k.ends_with('A'))
-        .map(|(_, v)| *v)
-        .collect();
-    let mut end_ids: Vec<_> = map
+// the first step at which every walker is on an end node at once
+fn first_meeting(walkers: &[Hits]) -> Result<Option<Int>> {
+    let Some(longest) = walkers.iter().max_by_key(|h| h.cycle_start) else {
+        return Ok(None);
+    };
+    let on_end = |h: &Hits, step: usize| {
+        if step < h.cycle_start {
+            h.prefix.contains(&step)
+        } else {
+            let step = h.cycle_start + (step - h.cycle_start) % h.period;
+            h.cycle.contains(&step)
+        }
+    };
+    // before the longest prefix ends a meeting is one of its hits
+    if let Some(&step) = longest
+        .prefix
         .iter()
-        .filter(|(k, _)| k.ends_with('Z'))
-        .map(|(_, v)| *v)
-        .collect();
-    end_ids.sort();
-    let mut start_to_end_steps = HashMap::new();
+        .find(|&&step| walkers.iter().all(|h| on_end(h, step)))
+    {
+        return Ok(Some(step as Int));
+    }
 
-    for &start in &start_ids {
-        start_to_end_steps.insert(start, get_steps(start, &end_ids, instrs, network)?);
+    // after it every walker is in its cycle, so a meeting solves one congruence per walker;
+    // the walkers are folded in one at a time
+    let mut solutions = vec![(0, 1)];
+    for h in walkers {
+        solutions = meet(&solutions, h)?;
+        if solutions.is_empty() {
+            return Ok(None);
+        }
+    }
+    let from = longest.cycle_start as Int;
+    let mut first = None;
+    for (x, m) in solutions {
+        // the first x + k * m at or after the longest prefix
+        let step = if x >= from {
+            x
+        } else {
+            add(x, mul((from - x).div_ceil(m), m)?)?
+        };
+        first = Some(first.map_or(step, |f: Int| f.min(step)));
     }
+    Ok(first)
+}
+
+fn part2(instrs: &[char], network: &Network, map: &NodeMap) -> Result<Int> {
+    let start = Instant::now();
+
+    let (starts, ends) = ("*A".parse()?, "*Z".parse()?);
+    let walkers: Vec<_> = walkers(&starts, &ends, instrs, network, map)?
+        .into_iter()
+        .map(|(_, hits)| hits)
+        .collect();
 
-    let steps = dfs(
-        &start_to_end_steps,
-        start_ids.len(),
-        end_ids.len(),
-        &mut HashSet::new(),
-        &mut HashSet::new(),
-    )
-    .unwrap();
+    let Some(steps) = first_meeting(&walkers)? else {
+        return err!("The ghosts are never all on end nodes at once");
+    };
 
     writeln!(io::stdout(), "Part 2: {steps}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
@@ -223,8 +405,35 @@ fn main() -> Result<()> {
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
+    let options = Options::from_args()?;
     let (instrs, network, map) = parse_input(&input)?;
 
+    if let Some((starts, ends)) = &options.dot {
+        let walkers = walkers(starts, ends, &instrs, &network, &map)?;
+        let dot = dot::to_dot(&instrs, &network, &map, &walkers, options.collapse)?;
+        write!(io::stdout(), "{dot}")?;
+        return Ok(());
+    }
+    if !options.queries.is_empty() {
+        for (starts, ends) in &options.queries {
+            writeln!(io::stdout(), "from {starts} to {ends}")?;
+            let walkers = walkers(starts, ends, &instrs, &network, &map)?;
+            for (name, hits) in &walkers {
+                let steps = hits.first().map_or("never".to_string(), |s| s.to_string());
+                writeln!(io::stdout(), "  {name} {steps}")?;
+            }
+            let hits: Vec<_> = walkers.into_iter().map(|(_, hits)| hits).collect();
+            let show = |steps: Option<Int>| steps.map_or("never".to_string(), |s| s.to_string());
+            writeln!(
+                io::stdout(),
+                "  any {}",
+                show(first_hit(&hits).map(|s| s as Int))
+            )?;
+            writeln!(io::stdout(), "  all {}", show(first_meeting(&hits)?))?;
+        }
+        return Ok(());
+    }
+
     part1(&instrs, &network, &map)?;
     part2(&instrs, &network, &map)?;
     Ok(())
@@ -258,6 +467,197 @@ XXX = (XXX, XXX)";
 
     let (instrs, network, map) = parse_input(input).unwrap();
     assert_eq!(part2(&instrs, &network, &map).unwrap(), 6);
+
+    let query = |starts: &str, ends: &str| {
+        let (starts, ends) = (starts.parse().unwrap(), ends.parse().unwrap());
+        walkers(&starts, &ends, &instrs, &network, &map).unwrap()
+    };
+    let walkers = query("re:[0-9]+A", "glob:??Z");
+    let steps: Vec<_> = walkers.iter().map(|(n, h)| (*n, h.first())).collect();
+    assert_eq!(steps, [("11A", Some(2)), ("22A", Some(3))]);
+    let hits: Vec<_> = walkers.into_iter().map(|(_, h)| h).collect();
+    assert_eq!(first_hit(&hits), Some(2));
+    assert_eq!(first_meeting(&hits).unwrap(), Some(6));
+
+    // both ghosts take their first step onto a B node
+    let hits: Vec<_> = query("*A", "re:(11|22)B")
+        .into_iter()
+        .map(|(_, h)| h)
+        .collect();
+    assert_eq!(first_meeting(&hits).unwrap(), Some(1));
+    let hits: Vec<_> = query("exact:11A", "22Z")
+        .into_iter()
+        .map(|(_, h)| h)
+        .collect();
+    assert_eq!(
+        (first_hit(&hits), first_meeting(&hits).unwrap()),
+        (None, None)
+    );
+    assert!(query("AAA", "*Z").is_empty());
+}
+
+#[test]
+fn dot_export() {
+    let input = "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)";
+    let (instrs, network, map) = parse_input(input).unwrap();
+    let (starts, ends) = ("*A".parse().unwrap(), "*Z".parse().unwrap());
+    let ghosts = walkers(&starts, &ends, &instrs, &network, &map).unwrap();
+    let dot = dot::to_dot(&instrs, &network, &map, &ghosts, None).unwrap();
+    let lines: Vec<_> = dot.lines().collect();
+    assert_eq!(
+        lines[1],
+        "  label=\"11A: cycle of 2 steps from step 1, ends at 2 + 2k, lcm holds\\l\
+         22A: cycle of 6 steps from step 1, ends at 3 + 6k, 6 + 6k, lcm holds\\l\";"
+    );
+    for line in [
+        "  \"11A\" [shape=box, color=red];",
+        "  \"11Z\" [shape=doublecircle, color=red, style=bold];",
+        "  \"22C\" [color=blue, style=bold];",
+        "  \"XXX\";",
+        "  \"11A\" -> \"11B\" [label=L, color=\"red\", penwidth=2];",
+        "  \"22B\" -> \"22C\" [label=L/R, color=\"blue\", penwidth=2];",
+        "  \"XXX\" -> \"XXX\" [label=L/R];",
+    ] {
+        assert!(lines.contains(&line), "{line}\n{dot}");
+    }
+
+    // B1 to B3 form a run with a single way in and out
+    let input = "L
+
+AAA = (B1, B1)
+B1 = (B2, B2)
+B2 = (B3, B3)
+B3 = (ZZZ, ZZZ)
+ZZZ = (ZZZ, ZZZ)";
+    let (instrs, network, map) = parse_input(input).unwrap();
+    let (starts, ends) = ("AAA".parse().unwrap(), "ZZZ".parse().unwrap());
+    let ghosts = walkers(&starts, &ends, &instrs, &network, &map).unwrap();
+    assert!(dot::to_dot(&instrs, &network, &map, &ghosts, None)
+        .unwrap()
+        .contains("lcm does not hold"));
+    let dot = dot::to_dot(&instrs, &network, &map, &ghosts, Some(3)).unwrap();
+    let lines: Vec<_> = dot.lines().collect();
+    for line in [
+        "  \"chain 0\" [label=\"B1 … B3\\n3 nodes\", shape=note, color=red];",
+        "  \"AAA\" -> \"chain 0\" [label=L/R, color=\"red\", penwidth=2];",
+        "  \"chain 0\" -> \"ZZZ\" [label=L/R, color=\"red\", penwidth=2];",
+        "  \"ZZZ\" -> \"ZZZ\" [label=L/R, color=\"red\", penwidth=2];",
+    ] {
+        assert!(lines.contains(&line), "{line}\n{dot}");
+    }
+    assert!(!dot.contains("\"B2\""));
+    let dot = dot::to_dot(&instrs, &network, &map, &ghosts, Some(4)).unwrap();
+    assert!(!dot.contains("chain"));
+
+    // a run may start at a node with two ways in and end at one with two ways out
+    let input = "L
+
+AAA = (B1, B1)
+XXX = (B1, B1)
+B1 = (B2, B2)
+B2 = (B3, B3)
+B3 = (ZZZ, YYY)
+YYY = (YYY, YYY)
+ZZZ = (ZZZ, ZZZ)";
+    let (instrs, network, map) = parse_input(input).unwrap();
+    let ghosts = walkers(&starts, &ends, &instrs, &network, &map).unwrap();
+    let dot = dot::to_dot(&instrs, &network, &map, &ghosts, Some(3)).unwrap();
+    let lines: Vec<_> = dot.lines().collect();
+    for line in [
+        "  \"chain 0\" [label=\"B1 … B3\\n3 nodes\", shape=note, color=red];",
+        "  \"XXX\" -> \"chain 0\" [label=L/R];",
+        "  \"chain 0\" -> \"YYY\" [label=R];",
+    ] {
+        assert!(lines.contains(&line), "{line}\n{dot}");
+    }
+
+    // a start that is an end is drawn once, a node without a name by its id
+    let network = vec![(0, 0), (0, 2), (1, 1)];
+    let map = NodeMap::from([("AAA".to_string(), 1), ("ZZZ".to_string(), 2)]);
+    let (starts, ends) = ("AAA".parse().unwrap(), "re:AAA|ZZZ".parse().unwrap());
+    let ghosts = walkers(&starts, &ends, &['R'], &network, &map).unwrap();
+    let dot = dot::to_dot(&['R'], &network, &map, &ghosts, None).unwrap();
+    let lines: Vec<_> = dot.lines().collect();
+    for line in [
+        "  \"AAA\" [shape=box, peripheries=2, color=red, style=bold];",
+        "  \"AAA\" -> \"0\" [label=L];",
+    ] {
+        assert!(lines.contains(&line), "{line}\n{dot}");
+    }
+}
+
+#[test]
+fn ghosts_with_offsets() {
+    assert_eq!(crt((2, 4), (4, 6)).unwrap(), Some((10, 12)));
+    assert_eq!(crt((1, 4), (2, 6)).unwrap(), None);
+    assert_eq!(crt((0, 1), (3, 7)).unwrap(), Some((3, 7)));
+    assert_eq!(inverse(3, 7), 5);
+    // only the hits that agree with the residue modulo 2 pair with it
+    let hits = Hits {
+        prefix: vec![],
+        cycle_start: 0,
+        period: 6,
+        cycle: vec![2, 3, 4, 5],
+    };
+    assert_eq!(meet(&[(1, 4)], &hits).unwrap(), [(5, 12), (9, 12)]);
+
+    let mut rng = xorshift::XorShift::new(0x2545_f491_4f6c_dd1d);
+    let mut rand = |n: usize| rng.below(n as u64) as usize;
+    for _ in 0..300 {
+        let nodes = 2 + rand(6);
+        let instrs: String = (0..1 + rand(3))
+            .map(|_| if rand(2) == 0 { 'L' } else { 'R' })
+            .collect();
+        let mut input = format!("{instrs}\n\n");
+        for n in 0..nodes {
+            input += &format!("N{n} = (N{}, N{})\n", rand(nodes), rand(nodes));
+        }
+        let (instrs, network, map) = parse_input(&input).unwrap();
+        let id = |n: usize| map[&format!("N{n}")];
+        let mut end_ids: Vec<_> = (0..nodes).filter(|_| rand(3) == 0).map(id).collect();
+        end_ids.sort();
+        let start_ids: Vec<_> = (0..1 + rand(3)).map(|_| id(rand(nodes))).collect();
+
+        let walkers: Vec<_> = start_ids
+            .iter()
+            .map(|&s| get_steps(s, &end_ids, &instrs, &network).unwrap())
+            .collect();
+        // a meeting repeats within the longest prefix plus the lcm of the periods
+        let bound = walkers.iter().map(|h| h.cycle_start).max().unwrap() as Int
+            + walkers
+                .iter()
+                .fold(1, |l, h| lcm(l, h.period as Int).unwrap());
+        let mut states: Vec<_> = start_ids.iter().map(|&s| (s, 0)).collect();
+        let mut brute = None;
+        for step in 0..bound {
+            if states.iter().all(|(n, _)| end_ids.contains(n)) {
+                brute = Some(step);
+                break;
+            }
+            for state in states.iter_mut() {
+                *state = next_node(*state, &instrs, &network).unwrap();
+            }
+        }
+        assert_eq!(first_meeting(&walkers).unwrap(), brute, "{input}");
+    }
+}
+
+#[cfg(feature = "checked")]
+#[test]
+fn checked_overflow() {
+    assert_eq!(lcm(4, 6).unwrap(), 12);
+    assert!(lcm(Int::MAX / 2 + 1, 3)
+        .unwrap_err()
+        .is::<checked::Overflow>());
 }
 
 #[test]
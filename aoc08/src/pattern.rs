@@ -0,0 +1,205 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use regex::Regex;
+
+macro_rules! err {
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
+}
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Char(char),
+    Any,
+    Star,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Char(x) => *x == c,
+            Token::Any => true,
+            Token::Class { ranges, negated } => {
+                ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+            }
+            Token::Star => false,
+        }
+    }
+}
+
+// `*` any run of characters, `?` any character, `[a-z]` and `[!a]` a class, matched against a
+// whole name
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glob {
+    tokens: Vec<Token>,
+}
+
+impl FromStr for Glob {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut chars = s.chars().peekable();
+        let mut tokens = vec![];
+        while let Some(c) = chars.next() {
+            tokens.push(match c {
+                '*' => Token::Star,
+                '?' => Token::Any,
+                '[' => {
+                    // the rest of a class after its `[`, e.g. `a-z0]`
+                    let negated = chars.next_if_eq(&'!').is_some();
+                    let mut ranges = vec![];
+                    loop {
+                        let lo = match chars.next() {
+                            None => return err!("unclosed class in glob {s:?}"),
+                            Some(']') if !ranges.is_empty() => break,
+                            Some(c) => c,
+                        };
+                        let hi = match chars.next_if_eq(&'-') {
+                            Some(_) => match chars.next() {
+                                None => return err!("unclosed class in glob {s:?}"),
+                                Some(']') => {
+                                    ranges.extend([(lo, lo), ('-', '-')]);
+                                    break;
+                                }
+                                Some(hi) => hi,
+                            },
+                            None => lo,
+                        };
+                        ranges.push((lo, hi));
+                    }
+                    Token::Class { ranges, negated }
+                }
+                c => Token::Char(c),
+            });
+        }
+        Ok(Glob { tokens })
+    }
+}
+
+impl Glob {
+    pub fn is_match(&self, name: &str) -> bool {
+        let text: Vec<_> = name.chars().collect();
+        let (mut p, mut t) = (0, 0);
+        // the last `*` seen and the text it has swallowed up to, it takes one more character
+        // whenever the rest fails
+        let mut star = None;
+        while t < text.len() {
+            match self.tokens.get(p) {
+                Some(Token::Star) => {
+                    star = Some((p, t));
+                    p += 1;
+                }
+                Some(token) if token.matches(text[t]) => {
+                    p += 1;
+                    t += 1;
+                }
+                _ => match star {
+                    Some((sp, st)) => {
+                        star = Some((sp, st + 1));
+                        p = sp + 1;
+                        t = st + 1;
+                    }
+                    None => return false,
+                },
+            }
+        }
+        self.tokens[p..].iter().all(|token| *token == Token::Star)
+    }
+}
+
+// which node names a query starts or ends on
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Exact(String),
+    Glob(String, Glob),
+    // anchored to the whole name
+    Regex(String, Regex),
+}
+
+impl Pattern {
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            Pattern::Exact(exact) => exact == name,
+            Pattern::Glob(_, glob) => glob.is_match(name),
+            Pattern::Regex(_, regex) => regex.is_match(name),
+        }
+    }
+}
+
+// patterns are equal when they are written the same
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = Box<dyn Error>;
+
+    // `re:<regex>`, `glob:<glob>` or `exact:<name>`, without a prefix a name with `*`, `?`
+    // or `[` is a glob
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(if let Some(re) = s.strip_prefix("re:") {
+            Pattern::Regex(re.to_string(), Regex::new(&format!("^(?:{re})$"))?)
+        } else if let Some(glob) = s.strip_prefix("glob:") {
+            Pattern::Glob(glob.to_string(), glob.parse()?)
+        } else if let Some(exact) = s.strip_prefix("exact:") {
+            Pattern::Exact(exact.to_string())
+        } else if s.contains(['*', '?', '[']) {
+            Pattern::Glob(s.to_string(), s.parse()?)
+        } else {
+            Pattern::Exact(s.to_string())
+        })
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Exact(exact) => write!(f, "exact:{exact}"),
+            Pattern::Glob(glob, _) => write!(f, "glob:{glob}"),
+            Pattern::Regex(re, _) => write!(f, "re:{re}"),
+        }
+    }
+}
+
+#[test]
+fn patterns() {
+    let cases = [
+        ("AAA", "AAA", true),
+        ("AAA", "AAAA", false),
+        ("*A", "11A", true),
+        ("*A", "11B", false),
+        ("glob:A*B*C", "AxxBxBxC", true),
+        ("glob:A*B*C", "AxxCxB", false),
+        ("glob:**", "", true),
+        ("glob:?[0-9]Z", "X1Z", true),
+        ("glob:?[!0-9]Z", "X1Z", false),
+        ("glob:[*]", "*", true),
+        ("glob:[a-]", "-", true),
+        ("exact:*A", "*A", true),
+        ("exact:*A", "11A", false),
+        ("re:..A", "11A", true),
+        ("re:..A", "1A", false),
+        ("re:(AA|ZZ)Z", "ZZZ", true),
+        ("re:(AA|ZZ)Z", "AZZ", false),
+        // anchored, so a match inside the name is not enough
+        ("re:A|Z", "AZ", false),
+        ("re:", "", true),
+    ];
+    for (pattern, name, expected) in cases {
+        let parsed: Pattern = pattern.parse().unwrap();
+        assert_eq!(parsed.matches(name), expected, "{pattern} {name}");
+        assert_eq!(parsed.to_string().parse::<Pattern>().unwrap(), parsed);
+    }
+    for bad in ["re:(a", "re:a)", "re:*a", "glob:[a", "glob:[!"] {
+        assert!(bad.parse::<Pattern>().is_err(), "{bad}");
+    }
+}
@@ -0,0 +1,310 @@
+use std::error::Error;
+use std::fmt;
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Debug)]
+pub struct Overflow {
+    op: char,
+    lhs: i128,
+    rhs: i128,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "arithmetic overflow: {} {} {}",
+            self.lhs, self.op, self.rhs
+        )
+    }
+}
+
+impl Error for Overflow {}
+
+fn add(lhs: i128, rhs: i128) -> Result<i128> {
+    lhs.checked_add(rhs)
+        .ok_or_else(|| Overflow { op: '+', lhs, rhs }.into())
+}
+
+fn sub(lhs: i128, rhs: i128) -> Result<i128> {
+    lhs.checked_sub(rhs)
+        .ok_or_else(|| Overflow { op: '-', lhs, rhs }.into())
+}
+
+fn mul(lhs: i128, rhs: i128) -> Result<i128> {
+    lhs.checked_mul(rhs)
+        .ok_or_else(|| Overflow { op: '*', lhs, rhs }.into())
+}
+
+fn div(lhs: i128, rhs: i128) -> Result<i128> {
+    lhs.checked_div(rhs)
+        .ok_or_else(|| Overflow { op: '/', lhs, rhs }.into())
+}
+
+// unsigned, as the gcd of i128::MIN and 0 is 2^127, which is past i128::MAX
+fn gcd(a: i128, b: i128) -> Result<i128> {
+    let (mut x, mut y) = (a.unsigned_abs(), b.unsigned_abs());
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    match i128::try_from(x) {
+        Ok(g) => Ok(g),
+        Err(_) => Err(format!("the gcd of {a} and {b} is 2^127, past i128::MAX").into()),
+    }
+}
+
+// a history whose differences never all reach zero: the only polynomials through it have
+// a degree of at least its length - 1, with no difference row left to confirm them
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotPolynomial {
+    // 1-based
+    pub history: usize,
+    pub len: usize,
+}
+
+impl fmt::Display for NotPolynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "history {} of {} values does not reach all zero differences",
+            self.history, self.len
+        )
+    }
+}
+
+impl Error for NotPolynomial {}
+
+// the coefficients of the polynomial of `degree` closest to the values at 0, 1, 2, ... in the
+// least-squares sense, from the constant up
+// see: https://en.wikipedia.org/wiki/Polynomial_regression#Matrix_form_and_calculation_of_estimates
+pub fn least_squares(values: &[i64], degree: usize) -> Result<Vec<f64>> {
+    let n = degree + 1;
+    // the normal equations (X^T X) c = X^T y as an augmented matrix
+    let mut rows = vec![vec![0.0; n + 1]; n];
+    for (x, &y) in values.iter().enumerate() {
+        let powers: Vec<_> = (0..n).map(|i| (x as f64).powi(i as i32)).collect();
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, p) in powers.iter().enumerate() {
+                row[j] += powers[i] * p;
+            }
+            row[n] += powers[i] * y as f64;
+        }
+    }
+    // gaussian elimination with partial pivoting
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&a, &b| rows[a][col].abs().total_cmp(&rows[b][col].abs()))
+            .unwrap();
+        if rows[pivot][col].abs() < 1e-9 {
+            return Err(format!(
+                "{} values do not determine a polynomial of degree {degree}",
+                values.len()
+            )
+            .into());
+        }
+        rows.swap(col, pivot);
+        let pivot = rows[col].clone();
+        for (r, row) in rows.iter_mut().enumerate() {
+            if r != col {
+                let factor = row[col] / pivot[col];
+                for (x, p) in row.iter_mut().zip(&pivot).skip(col) {
+                    *x -= factor * p;
+                }
+            }
+        }
+    }
+    Ok(rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| row[n] / row[i])
+        .collect())
+}
+
+// the highest degree whose coefficients can be found, their denominators are up to degree!
+// and 34! is beyond an i128
+pub const MAX_DEGREE: usize = 33;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DegreeTooHigh {
+    pub degree: usize,
+}
+
+impl fmt::Display for DegreeTooHigh {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the coefficients of a polynomial of degree {} need {}!, at most degree {MAX_DEGREE} fits in an i128",
+            self.degree, self.degree
+        )
+    }
+}
+
+impl Error for DegreeTooHigh {}
+
+// a fraction in lowest terms with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    pub num: i128,
+    pub den: i128,
+}
+
+impl Rational {
+    // `den` must not be zero
+    fn new(num: i128, den: i128) -> Result<Self> {
+        let g = gcd(num, den)?.max(1) * den.signum();
+        Ok(Rational {
+            num: div(num, g)?,
+            den: div(den, g)?,
+        })
+    }
+
+    fn add(self, other: Rational) -> Result<Rational> {
+        // over the lcm of the denominators to keep the numbers small
+        let g = gcd(self.den, other.den)?;
+        let den = mul(self.den / g, other.den)?;
+        let num = add(mul(self.num, other.den / g)?, mul(other.num, self.den / g)?)?;
+        Rational::new(num, den)
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+// the polynomial of the lowest degree through the values of a history at 0, 1, 2, ...
+#[derive(Debug, Clone, PartialEq)]
+pub struct History {
+    // the first value of every difference row, the coefficients of the newton form
+    // p(k) = sum of newton[j] * C(k, j)
+    // see: https://en.wikipedia.org/wiki/Newton_polynomial#Newton_forward_divided_difference_formula
+    newton: Vec<i128>,
+    len: usize,
+}
+
+impl History {
+    pub fn new(values: &[i64]) -> Result<Self> {
+        let len = values.len();
+        let mut newton = vec![];
+        let mut row: Vec<i128> = values.iter().map(|&v| v as i128).collect();
+        while let Some(&first) = row.first() {
+            newton.push(first);
+            row = row
+                .windows(2)
+                .map(|w| sub(w[1], w[0]))
+                .collect::<Result<_>>()?;
+        }
+        // the trailing zero differences do not change the polynomial
+        while newton.last() == Some(&0) {
+            newton.pop();
+        }
+        Ok(History { newton, len })
+    }
+
+    // whether some difference row is all zero, so the polynomial has a degree below the
+    // length - 1 and the history confirms it
+    pub fn converges(&self) -> bool {
+        self.newton.len() < self.len
+    }
+
+    // the zero polynomial has degree 0
+    pub fn degree(&self) -> usize {
+        self.newton.len().saturating_sub(1)
+    }
+
+    // c[i] of p(k) = sum of c[i] * k^i, from the constant up
+    pub fn coefficients(&self) -> Result<Vec<Rational>> {
+        if self.degree() > MAX_DEGREE {
+            return Err(DegreeTooHigh {
+                degree: self.degree(),
+            }
+            .into());
+        }
+        let mut coefficients = vec![Rational { num: 0, den: 1 }; self.degree() + 1];
+        // k (k - 1) ... (k - j + 1) as integer coefficients, and j!
+        let mut falling = vec![1];
+        let mut factorial = 1;
+        for (j, &d) in self.newton.iter().enumerate() {
+            if j > 0 {
+                factorial = mul(factorial, j as i128)?;
+            }
+            for (c, &f) in coefficients.iter_mut().zip(&falling) {
+                *c = c.add(Rational::new(mul(d, f)?, factorial)?)?;
+            }
+            // multiply by (k - j)
+            let mut next = vec![0; falling.len() + 1];
+            for (i, &f) in falling.iter().enumerate() {
+                next[i + 1] = add(next[i + 1], f)?;
+                next[i] = sub(next[i], mul(f, j as i128)?)?;
+            }
+            falling = next;
+        }
+        Ok(coefficients)
+    }
+
+    // the value at any index, the history starts at 0 and an index before it is negative
+    pub fn value_at(&self, k: i128) -> Result<i128> {
+        let mut value = 0;
+        // C(k, j), which is an integer for negative k too
+        let mut binomial = 1;
+        for (j, &d) in self.newton.iter().enumerate() {
+            if j > 0 {
+                // C(k, j) = C(k, j - 1) * (k - j + 1) / j, the division is exact
+                let j = j as i128;
+                let g = gcd(binomial, j)?;
+                binomial = mul(binomial / g, sub(k, j - 1)? / (j / g))?;
+            }
+            value = add(value, mul(d, binomial)?)?;
+        }
+        Ok(value)
+    }
+}
+
+// c[i] of p(k) = sum of c[i] * k^i, from the constant up, e.g. `1/2 k^2 + 3/2 k - 1`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial(pub Vec<Rational>);
+
+impl fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let coefficients = &self.0;
+        let mut terms = coefficients
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(i, c)| c.num != 0 || *i == 0 && coefficients.len() == 1);
+        let term = |i: usize, c: String| match i {
+            0 => c,
+            1 => format!("{c} k"),
+            _ => format!("{c} k^{i}"),
+        };
+        if let Some((i, c)) = terms.next() {
+            write!(f, "{}", term(i, c.to_string()))?;
+        }
+        for (i, c) in terms {
+            let sign = if c.num < 0 { '-' } else { '+' };
+            // unsigned, as i128::MIN has no positive i128
+            let magnitude = match c.den {
+                1 => c.num.unsigned_abs().to_string(),
+                den => format!("{}/{den}", c.num.unsigned_abs()),
+            };
+            write!(f, " {sign} {}", term(i, magnitude))?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn gcd_of_extremes() {
+    assert_eq!(gcd(-12, 18).unwrap(), 6);
+    assert_eq!(gcd(i128::MIN, -1).unwrap(), 1);
+    assert_eq!(gcd(i128::MIN, 2).unwrap(), 2);
+    assert!(gcd(i128::MIN, 0).is_err());
+    assert!(Rational::new(i128::MIN, -1).is_err());
+    assert_eq!(Rational::new(6, -4).unwrap(), Rational { num: -3, den: 2 });
+}
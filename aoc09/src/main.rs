@@ -2,13 +2,71 @@ use std::error::Error;
 use std::io::{self, Read, Write};
 use std::time::Instant;
 
-#[allow(unused_macros)]
+use history::{least_squares, History, NotPolynomial, Polynomial};
+
+mod history;
+#[cfg(test)]
+#[path = "../../common/xorshift.rs"]
+mod xorshift;
+
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
 }
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Fallback {
+    // the least-squares polynomial of a degree
+    LeastSquares(usize),
+}
+
+impl std::str::FromStr for Fallback {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once(':') {
+            Some(("least-squares", degree)) => Ok(Fallback::LeastSquares(degree.parse()?)),
+            _ => err!("unknown fallback {s:?}, expect least-squares:<degree>"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Options {
+    at: Vec<i128>,
+    polynomials: bool,
+    check: bool,
+    fallback: Option<Fallback>,
+}
+
+impl Options {
+    // `--at <k>` prints the sum of every history's value at index k, the first value is at 0,
+    // `--polynomials` prints the polynomial of every history, `--check` whether it converges,
+    // `--fallback least-squares:<degree>` extrapolates the histories that do not converge
+    // from a least-squares fit instead of failing
+    fn from_args() -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing a value of {arg}"));
+            match arg.as_str() {
+                "--at" => options.at.push(value()?.parse()?),
+                "--polynomials" => options.polynomials = true,
+                "--check" => options.check = true,
+                "--fallback" => options.fallback = Some(value()?.parse()?),
+                _ => {
+                    return err!(
+                        "unknown argument {arg:?}, expect --at <k>, --polynomials, --check \
+                         or --fallback least-squares:<degree>"
+                    )
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
 fn parse_input(input: &str) -> Vec<Vec<i64>> {
     input
         .lines()
@@ -21,52 +79,114 @@ fn parse_input(input: &str) -> Vec<Vec<i64>> {
         .collect()
 }
 
-fn difference(values: &[i64]) -> Vec<i64> {
-    values.windows(2).map(|w| w[1] - w[0]).collect()
+fn difference(values: &[i64]) -> Result<Vec<i64>> {
+    values
+        .windows(2)
+        .map(|w| match w[1].checked_sub(w[0]) {
+            Some(d) => Ok(d),
+            None => err!("the difference {} - {} overflows an i64", w[1], w[0]),
+        })
+        .collect()
 }
 
 fn all_zero(values: &[i64]) -> bool {
     values.iter().all(|n| n == &0)
 }
 
-fn predict_last(history: &[i64]) -> i64 {
-    let mut last = 0;
+// None when the differences run out before they are all zero
+fn predict_last(history: &[i64]) -> Result<Option<i64>> {
+    let mut last: i64 = 0;
 
     let mut values = history.to_vec();
     while !all_zero(&values) {
-        last += *values.last().unwrap();
-        values = difference(&values);
+        last = last
+            .checked_add(*values.last().unwrap())
+            .ok_or("the prediction overflows an i64")?;
+        values = difference(&values)?;
     }
-    last
+    Ok((!values.is_empty()).then_some(last))
 }
 
-fn part1(histories: &[Vec<i64>]) -> Result<i64> {
+// the value at index k of a history that does not converge, the history is 1-based
+fn fall_back(history: usize, values: &[i64], k: i128, fallback: Option<Fallback>) -> Result<i128> {
+    match fallback {
+        Some(Fallback::LeastSquares(degree)) => {
+            let coefficients = least_squares(values, degree)?;
+            let value = coefficients
+                .iter()
+                .rev()
+                .fold(0.0, |v, c| v * k as f64 + c)
+                .round();
+            // `as` would saturate a value out of range and turn NaN into 0, 2^127 itself is
+            // already past i128::MAX
+            if !value.is_finite() || value < i128::MIN as f64 || value >= i128::MAX as f64 {
+                return err!("history {history}: the fitted value {value} at {k} is not an i128");
+            }
+            Ok(value as i128)
+        }
+        None => Err(NotPolynomial {
+            history,
+            len: values.len(),
+        }
+        .into()),
+    }
+}
+
+fn to_i64(history: usize, value: i128) -> Result<i64> {
+    match i64::try_from(value) {
+        Ok(value) => Ok(value),
+        Err(_) => err!("history {history}: the fitted value {value} is not an i64"),
+    }
+}
+
+fn part1(histories: &[Vec<i64>], fallback: Option<Fallback>) -> Result<i64> {
     let start = Instant::now();
 
-    let result = histories.iter().map(|h| predict_last(h)).sum();
+    let mut result: i64 = 0;
+    for (i, h) in histories.iter().enumerate() {
+        let last = match predict_last(h)? {
+            Some(last) => last,
+            None => to_i64(i + 1, fall_back(i + 1, h, h.len() as i128, fallback)?)?,
+        };
+        result = result.checked_add(last).ok_or("the sum overflows an i64")?;
+    }
 
     writeln!(io::stdout(), "Part 1: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
     Ok(result)
 }
 
-fn predict_first(history: &[i64]) -> i64 {
-    let mut first = 0;
-    let mut sign = 1;
+// None when the differences run out before they are all zero
+fn predict_first(history: &[i64]) -> Result<Option<i64>> {
+    let mut first: i64 = 0;
+    let mut add = true;
 
     let mut values = history.to_vec();
     while !all_zero(&values) {
-        first += sign * values[0];
-        sign *= -1;
-        values = difference(&values);
+        first = match add {
+            true => first.checked_add(values[0]),
+            false => first.checked_sub(values[0]),
+        }
+        .ok_or("the prediction overflows an i64")?;
+        add = !add;
+        values = difference(&values)?;
     }
-    first
+    Ok((!values.is_empty()).then_some(first))
 }
 
-fn part2(histories: &[Vec<i64>]) -> Result<i64> {
+fn part2(histories: &[Vec<i64>], fallback: Option<Fallback>) -> Result<i64> {
     let start = Instant::now();
 
-    let result = histories.iter().map(|h| predict_first(h)).sum();
+    let mut result: i64 = 0;
+    for (i, h) in histories.iter().enumerate() {
+        let first = match predict_first(h)? {
+            Some(first) => first,
+            None => to_i64(i + 1, fall_back(i + 1, h, -1, fallback)?)?,
+        };
+        result = result
+            .checked_add(first)
+            .ok_or("the sum overflows an i64")?;
+    }
 
     writeln!(io::stdout(), "Part 2: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", start.elapsed())?;
@@ -77,9 +197,48 @@ fn main() -> Result<()> {
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
+    let options = Options::from_args()?;
     let histories = parse_input(&input);
-    part1(&histories)?;
-    part2(&histories)?;
+
+    if options.polynomials || options.check || !options.at.is_empty() {
+        let polynomials = histories
+            .iter()
+            .map(|h| History::new(h))
+            .collect::<Result<Vec<_>>>()?;
+        for (i, p) in polynomials.iter().enumerate() {
+            let fits = if p.converges() {
+                format!("degree {}", p.degree())
+            } else {
+                "does not converge".to_string()
+            };
+            if options.polynomials {
+                // a history that is too long for its coefficients still gets a line
+                let polynomial = match p.coefficients() {
+                    Ok(coefficients) => format!("p(k) = {}", Polynomial(coefficients)),
+                    Err(e) => format!("no coefficients, {e}"),
+                };
+                writeln!(io::stdout(), "{}: {fits} {polynomial}", i + 1)?;
+            } else if options.check {
+                writeln!(io::stdout(), "{}: {fits}", i + 1)?;
+            }
+        }
+        for &k in &options.at {
+            let mut sum: i128 = 0;
+            for (i, (p, h)) in polynomials.iter().zip(&histories).enumerate() {
+                let value = if p.converges() {
+                    p.value_at(k)?
+                } else {
+                    fall_back(i + 1, h, k, options.fallback)?
+                };
+                sum = sum.checked_add(value).ok_or("the sum overflows an i128")?;
+            }
+            writeln!(io::stdout(), "at {k}: {sum}")?;
+        }
+        return Ok(());
+    }
+
+    part1(&histories, options.fallback)?;
+    part2(&histories, options.fallback)?;
     Ok(())
 }
 
@@ -89,14 +248,152 @@ fn example_input() {
 1 3 6 10 15 21
 10 13 16 21 30 45";
     let histories = parse_input(input);
-    assert_eq!(part1(&histories).unwrap(), 114);
-    assert_eq!(part2(&histories).unwrap(), 2);
+    assert_eq!(part1(&histories, None).unwrap(), 114);
+    assert_eq!(part2(&histories, None).unwrap(), 2);
+}
+
+#[test]
+fn not_converging() {
+    let histories = parse_input("0 3 6 9 12 15\n1 2 4 8 16\n7");
+    let err = part1(&histories, None).unwrap_err();
+    assert_eq!(
+        *err.downcast_ref::<NotPolynomial>().unwrap(),
+        NotPolynomial { history: 2, len: 5 }
+    );
+    assert_eq!(
+        err.to_string(),
+        "history 2 of 5 values does not reach all zero differences"
+    );
+    assert!(part2(&histories[2..], None).is_err());
+    let converges: Vec<_> = histories
+        .iter()
+        .map(|h| History::new(h).unwrap().converges())
+        .collect();
+    assert_eq!(converges, [true, false, false]);
+    assert!(History::new(&[7, 7]).unwrap().converges());
+    assert!(!History::new(&[]).unwrap().converges());
+
+    // y = 3.6 x - 1 fits 1 2 4 8 16 best, and a constant fits 7
+    let linear = Some(Fallback::LeastSquares(1));
+    assert_eq!(part1(&histories[1..2], linear).unwrap(), 17);
+    assert_eq!(part2(&histories[1..2], linear).unwrap(), -5);
+    assert!(part1(&histories, linear).is_err());
+    let constant = Some(Fallback::LeastSquares(0));
+    // the mean 6.2 of 1 2 4 8 16 rounds to 6
+    assert_eq!(part1(&histories, constant).unwrap(), 18 + 6 + 7);
+    // a degree of the length - 1 goes through every value
+    let exact = Some(Fallback::LeastSquares(4));
+    assert_eq!(part1(&histories[1..2], exact).unwrap(), 31);
+    assert_eq!(
+        "least-squares:2".parse::<Fallback>().unwrap(),
+        Fallback::LeastSquares(2)
+    );
+    assert!("spline".parse::<Fallback>().is_err());
+
+    // a fitted value out of range is an error rather than saturated
+    let steep = [0, i64::MAX / 2];
+    assert!(fall_back(1, &steep, i128::MAX, linear).is_err());
+    // fits an i128 but not an i64
+    let value = fall_back(1, &steep, 4, linear).unwrap();
+    assert!(value > i64::MAX as i128);
+    assert!(to_i64(1, value).is_err());
+    // and so are the differences, predictions and sums past an i64
+    assert!(part1(&[vec![0, i64::MAX]], None).is_err());
+    assert!(part2(&[vec![i64::MIN, 0]], None).is_err());
+    assert!(part1(&[vec![i64::MIN, i64::MAX]], None).is_err());
+    assert!(part1(&[vec![i64::MAX], vec![1]], None).is_err());
+    assert!(part2(&[vec![i64::MIN], vec![-1]], None).is_err());
+}
+
+#[test]
+fn polynomials() {
+    let p = History::new(&[1, 3, 6, 10, 15, 21]).unwrap();
+    assert_eq!(p.degree(), 2);
+    let show = |p: &History| Polynomial(p.coefficients().unwrap()).to_string();
+    assert_eq!(show(&p), "1/2 k^2 + 3/2 k + 1");
+    assert_eq!(p.value_at(6).unwrap(), 28);
+    assert_eq!(p.value_at(-1).unwrap(), 0);
+    assert_eq!(p.value_at(-3).unwrap(), 1);
+    // (k + 1) (k + 2) / 2 far out
+    let k = 10i128.pow(18);
+    assert_eq!(p.value_at(k).unwrap(), (k + 1) * (k + 2) / 2);
+    assert!(p.value_at(i128::MAX / 2).is_err());
+
+    assert_eq!(show(&History::new(&[]).unwrap()), "0");
+    assert_eq!(History::new(&[0, 0]).unwrap().degree(), 0);
+    assert_eq!(show(&History::new(&[5, 4, 3]).unwrap()), "-1 k + 5");
+    assert_eq!(
+        Polynomial(vec![
+            history::Rational { num: 1, den: 1 },
+            history::Rational {
+                num: i128::MIN,
+                den: 1
+            }
+        ])
+        .to_string(),
+        format!("{} k + 1", i128::MIN)
+    );
+
+    // C(k, 33) has the highest degree with coefficients, 2^k is a history that never
+    // converges with a degree one below its length
+    let binomial: Vec<_> = (0..34).map(|k| (k == 33) as i64).collect();
+    let p = History::new(&binomial).unwrap();
+    assert_eq!(p.degree(), history::MAX_DEGREE);
+    assert_eq!(p.value_at(34).unwrap(), 34);
+    assert!(p.coefficients().is_ok());
+    let doubling: Vec<_> = (0..40).map(|k| 1 << k).collect();
+    let p = History::new(&doubling).unwrap();
+    assert_eq!(
+        *p.coefficients()
+            .unwrap_err()
+            .downcast::<history::DegreeTooHigh>()
+            .unwrap(),
+        history::DegreeTooHigh { degree: 39 }
+    );
+
+    let mut rng = xorshift::XorShift::new(0x2545_f491_4f6c_dd1d);
+    let mut rand = |n: u64| rng.below(n) as i64;
+    for _ in 0..500 {
+        // a random integer polynomial sampled past its degree, as the puzzle histories are
+        let c: Vec<_> = (0..1 + rand(6)).map(|_| rand(41) - 20).collect();
+        let at = |k: i128| c.iter().rev().fold(0, |v, &c| v * k + c as i128);
+        let len = c.len() + 1 + rand(5) as usize;
+        let history: Vec<_> = (0..len as i128).map(|k| at(k) as i64).collect();
+
+        let p = History::new(&history).unwrap();
+        let case = format!("{history:?}");
+        assert!(p.converges(), "{case}");
+        assert_eq!(
+            Some(p.value_at(len as i128).unwrap() as i64),
+            predict_last(&history).unwrap(),
+            "{case}"
+        );
+        assert_eq!(
+            Some(p.value_at(-1).unwrap() as i64),
+            predict_first(&history).unwrap(),
+            "{case}"
+        );
+        for k in [-1000, 12_345, 1_000_000] {
+            assert_eq!(p.value_at(k).unwrap(), at(k), "{case} at {k}");
+        }
+        let coefficients = p.coefficients().unwrap();
+        let expected: Vec<_> = c.iter().rev().skip_while(|&&c| c == 0).collect();
+        let found: Vec<_> = coefficients
+            .iter()
+            .rev()
+            .skip_while(|c| c.num == 0)
+            .collect();
+        assert_eq!(found.len(), expected.len(), "{case}");
+        for (found, &&expected) in found.iter().zip(&expected) {
+            assert_eq!((found.num, found.den), (expected as i128, 1), "{case}");
+        }
+    }
 }
 
 #[test]
 fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
     let histories = parse_input(&input);
-    assert_eq!(part1(&histories).unwrap(), 2175229206);
-    assert_eq!(part2(&histories).unwrap(), 942);
+    assert_eq!(part1(&histories, None).unwrap(), 2175229206);
+    assert_eq!(part2(&histories, None).unwrap(), 942);
 }
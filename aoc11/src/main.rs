@@ -9,6 +9,17 @@ macro_rules! err {
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
+// the integer type of the hot arithmetic, `--features wide` switches it to 128 bits
+#[cfg(not(feature = "wide"))]
+type Int = usize;
+#[cfg(feature = "wide")]
+type Int = u128;
+
+#[path = "../../common/checked.rs"]
+mod checked;
+
+use checked::{add, mul, sub};
+
 type Coord = (usize, usize);
 
 #[derive(Clone)]
@@ -54,31 +65,33 @@ impl Image {
             .collect()
     }
 
-    fn expansion(&self, pos: &Coord, expansion_rate: usize) -> Coord {
-        let empty_row = self.empty_rows[pos.0];
-        let empty_column = self.empty_columns[pos.1];
-        (
-            pos.0 + empty_row * (expansion_rate - 1),
-            pos.1 + empty_column * (expansion_rate - 1),
-        )
+    fn expansion(&self, pos: &Coord, expansion_rate: Int) -> Result<(Int, Int)> {
+        let empty_row = self.empty_rows[pos.0] as Int;
+        let empty_column = self.empty_columns[pos.1] as Int;
+        // every empty row or column before the position adds `expansion_rate - 1`
+        let extra = sub(expansion_rate, 1)?;
+        Ok((
+            add(pos.0 as Int, mul(empty_row, extra)?)?,
+            add(pos.1 as Int, mul(empty_column, extra)?)?,
+        ))
     }
 
-    fn shortest_path_sum(&self, expansion_rate: usize) -> usize {
-        fn dis(p1: &Coord, p2: &Coord) -> usize {
-            p1.0.abs_diff(p2.0) + p1.1.abs_diff(p2.1)
+    fn shortest_path_sum(&self, expansion_rate: Int) -> Result<Int> {
+        fn dis(p1: &(Int, Int), p2: &(Int, Int)) -> Result<Int> {
+            add(p1.0.abs_diff(p2.0), p1.1.abs_diff(p2.1))
         }
         let mut sum = 0;
         let galaxies: Vec<_> = (0..self.raw.len())
             .flat_map(|x| (0..self.bound.1).map(move |y| (x, y)))
             .filter(|(x, y)| self.raw[*x][*y])
             .map(|p| self.expansion(&p, expansion_rate))
-            .collect();
+            .collect::<Result<_>>()?;
         for i in 0..galaxies.len() {
             for j in i + 1..galaxies.len() {
-                sum += dis(&galaxies[i], &galaxies[j])
+                sum = add(sum, dis(&galaxies[i], &galaxies[j])?)?;
             }
         }
-        sum
+        Ok(sum)
     }
 }
 
@@ -104,33 +117,26 @@ fn parse_input<T: AsRef<str>>(input: T) -> Image {
         .as_ref()
         .lines()
         .filter(|l| !l.trim().is_empty())
-        .enumerate()
-        .map(|(_, l)| {
-            l.trim()
-                .chars()
-                .enumerate()
-                .map(move |(_, c)| c == '#')
-                .collect::<Vec<_>>()
-        })
+        .map(|l| l.trim().chars().map(|c| c == '#').collect::<Vec<_>>())
         .collect();
 
     Image::new(image)
 }
 
-fn part1(image: &Image) -> Result<usize> {
+fn part1(image: &Image) -> Result<Int> {
     let _start = Instant::now();
 
-    let sum = image.shortest_path_sum(2);
+    let sum = image.shortest_path_sum(2)?;
 
     writeln!(io::stdout(), "Part 1: {sum}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", _start.elapsed())?;
     Ok(sum)
 }
 
-fn part2(image: &Image) -> Result<usize> {
+fn part2(image: &Image) -> Result<Int> {
     let _start = Instant::now();
 
-    let sum = image.shortest_path_sum(1000000);
+    let sum = image.shortest_path_sum(1000000)?;
 
     writeln!(io::stdout(), "Part 2: {sum}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", _start.elapsed())?;
@@ -163,8 +169,23 @@ fn example_input() {
     let image = parse_input(input);
     println!("{:?}", image);
     assert_eq!(part1(&image).unwrap(), 374);
-    assert_eq!(image.shortest_path_sum(10), 1030);
-    assert_eq!(image.shortest_path_sum(100), 8410);
+    assert_eq!(image.shortest_path_sum(10).unwrap(), 1030);
+    assert_eq!(image.shortest_path_sum(100).unwrap(), 8410);
+}
+
+#[cfg(feature = "checked")]
+#[test]
+fn checked_overflow() {
+    let image = parse_input("#.\n..\n.#");
+    assert_eq!(image.shortest_path_sum(2).unwrap(), 4);
+    assert!(image
+        .shortest_path_sum(Int::MAX)
+        .unwrap_err()
+        .is::<checked::Overflow>());
+    assert!(image
+        .shortest_path_sum(0)
+        .unwrap_err()
+        .is::<checked::Overflow>());
 }
 
 #[test]
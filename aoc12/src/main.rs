@@ -4,6 +4,11 @@ use std::io::{self, Read, Write};
 use std::iter::once;
 use std::time::Instant;
 
+#[path = "../../common/explain.rs"]
+mod explain;
+
+use explain::{Explain, Trace};
+
 #[allow(unused_macros)]
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
@@ -11,6 +16,17 @@ macro_rules! err {
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
+// the integer type of the hot arithmetic, `--features wide` switches it to 128 bits
+#[cfg(not(feature = "wide"))]
+type Int = usize;
+#[cfg(feature = "wide")]
+type Int = u128;
+
+#[path = "../../common/checked.rs"]
+mod checked;
+
+use checked::add;
+
 fn parse_input<T: AsRef<str>>(input: T) -> Vec<(Vec<char>, Vec<usize>)> {
     let mut result = vec![];
     for line in input.as_ref().lines().filter(|l| !l.trim().is_empty()) {
@@ -31,9 +47,9 @@ fn count_arrangement(
     curr_spring: char,
     springs: &[char],
     counters: &[usize],
-    cache: &mut HashMap<(usize, usize), usize>,
-) -> usize {
-    match curr_spring {
+    cache: &mut HashMap<(usize, usize), Int>,
+) -> Result<Int> {
+    Ok(match curr_spring {
         '#' => {
             let remain = counters[0] - 1;
             if remain <= springs.len()
@@ -42,11 +58,11 @@ fn count_arrangement(
             {
                 // skip ahead
                 if remain == springs.len() {
-                    (counters.len() == 1) as usize
+                    (counters.len() == 1) as Int
                 } else if counters.len() == 1 {
-                    springs[remain + 1..].iter().all(|c| c != &'#') as usize
+                    springs[remain + 1..].iter().all(|c| c != &'#') as Int
                 } else {
-                    count_arrangement('.', &springs[remain + 1..], &counters[1..], cache)
+                    count_arrangement('.', &springs[remain + 1..], &counters[1..], cache)?
                 }
             } else {
                 0
@@ -54,7 +70,7 @@ fn count_arrangement(
         }
         '.' => {
             if let Some(i) = (0..springs.len()).find(|&i| springs[i] != '.') {
-                count_arrangement(springs[i], &springs[i + 1..], counters, cache)
+                count_arrangement(springs[i], &springs[i + 1..], counters, cache)?
             } else {
                 0
             }
@@ -63,17 +79,19 @@ fn count_arrangement(
             if let Some(&c) = cache.get(&(springs.len(), counters.len())) {
                 c
             } else {
-                let c = count_arrangement('#', springs, counters, cache)
-                    + count_arrangement('.', springs, counters, cache);
+                let c = add(
+                    count_arrangement('#', springs, counters, cache)?,
+                    count_arrangement('.', springs, counters, cache)?,
+                )?;
                 cache.insert((springs.len(), counters.len()), c);
                 c
             }
         }
         _ => unreachable!("Wrong spring record: {:?}", springs),
-    }
+    })
 }
 
-fn count_arrangement_with_unfold(springs: &[char], counters: &[usize], rate: usize) -> usize {
+fn count_arrangement_with_unfold(springs: &[char], counters: &[usize], rate: usize) -> Result<Int> {
     let s: Vec<_> = springs
         .iter()
         .cloned()
@@ -90,33 +108,55 @@ fn count_arrangement_with_unfold(springs: &[char], counters: &[usize], rate: usi
     count_arrangement(s[0], &s[1..], &c, &mut HashMap::new())
 }
 
-fn part1(records: &[(Vec<char>, Vec<usize>)]) -> Result<usize> {
+fn part1(records: &[(Vec<char>, Vec<usize>)], trace: &mut Trace) -> Result<Int> {
     let _start = Instant::now();
 
     let result = records
         .iter()
-        .map(|(s, c)| count_arrangement_with_unfold(s, c, 1))
-        .sum();
+        .enumerate()
+        .map(|(i, (s, c))| {
+            let count = count_arrangement_with_unfold(s, c, 1)?;
+            trace.record("part1.record", || {
+                vec![
+                    ("record", (i + 1).to_string()),
+                    ("springs", s.iter().collect()),
+                    ("count", count.to_string()),
+                ]
+            });
+            Ok(count)
+        })
+        .sum::<Result<_>>()?;
 
     writeln!(io::stdout(), "Part 1: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", _start.elapsed())?;
     Ok(result)
 }
 
-fn part2(records: &[(Vec<char>, Vec<usize>)]) -> Result<usize> {
+fn part2(records: &[(Vec<char>, Vec<usize>)], trace: &mut Trace) -> Result<Int> {
     let _start = Instant::now();
 
     let result = records
         .iter()
-        .map(|(s, c)| count_arrangement_with_unfold(s, c, 5))
-        .sum();
+        .enumerate()
+        .map(|(i, (s, c))| {
+            let count = count_arrangement_with_unfold(s, c, 5)?;
+            trace.record("part2.record", || {
+                vec![
+                    ("record", (i + 1).to_string()),
+                    ("springs", s.iter().collect()),
+                    ("count", count.to_string()),
+                ]
+            });
+            Ok(count)
+        })
+        .sum::<Result<_>>()?;
 
     writeln!(io::stdout(), "Part 2: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", _start.elapsed())?;
     Ok(result)
 }
 
-fn count_arrangement_dp(springs: &[char], counters: &[usize]) -> usize {
+fn count_arrangement_dp(springs: &[char], counters: &[usize]) -> Result<Int> {
     let length = springs
         .iter()
         .enumerate()
@@ -145,31 +185,35 @@ fn count_arrangement_dp(springs: &[char], counters: &[usize]) -> usize {
                 chunk = 0;
             }
             if c != '#' {
-                n_dp[i + 1] += n_dp[i];
+                n_dp[i + 1] = add(n_dp[i + 1], n_dp[i])?;
             }
             if chunk >= cnt && (i == cnt || springs[i - cnt - 1] != '#') {
-                n_dp[i + 1] += dp[i - cnt];
+                n_dp[i + 1] = add(n_dp[i + 1], dp[i - cnt])?;
             }
         }
         dp = n_dp;
     }
-    *dp.last().unwrap()
+    Ok(*dp.last().unwrap())
 }
 
-fn part1_dp(records: &[(Vec<char>, Vec<usize>)]) -> Result<usize> {
+fn part1_dp(records: &[(Vec<char>, Vec<usize>)]) -> Result<Int> {
     let _start = Instant::now();
 
     let result = records
         .iter()
         .map(|(s, c)| count_arrangement_dp(s, c))
-        .sum();
+        .sum::<Result<_>>()?;
 
     writeln!(io::stdout(), "Part 1 with DP: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", _start.elapsed())?;
     Ok(result)
 }
 
-fn count_arrangement_dp_with_unfold(springs: &[char], counters: &[usize], rate: usize) -> usize {
+fn count_arrangement_dp_with_unfold(
+    springs: &[char],
+    counters: &[usize],
+    rate: usize,
+) -> Result<Int> {
     let s: Vec<_> = springs
         .iter()
         .cloned()
@@ -186,13 +230,13 @@ fn count_arrangement_dp_with_unfold(springs: &[char], counters: &[usize], rate:
     count_arrangement_dp(&s, &c)
 }
 
-fn part2_dp(records: &[(Vec<char>, Vec<usize>)]) -> Result<usize> {
+fn part2_dp(records: &[(Vec<char>, Vec<usize>)]) -> Result<Int> {
     let _start = Instant::now();
 
     let result = records
         .iter()
         .map(|(s, c)| count_arrangement_dp_with_unfold(s, c, 5))
-        .sum();
+        .sum::<Result<_>>()?;
 
     writeln!(io::stdout(), "Part 2: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", _start.elapsed())?;
@@ -200,15 +244,21 @@ fn part2_dp(records: &[(Vec<char>, Vec<usize>)]) -> Result<usize> {
 }
 
 fn main() -> Result<()> {
+    let explain = Explain::from_args()?;
+    let mut trace = Trace::new(explain.is_some());
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
     let records = parse_input(input);
-    part1(&records)?;
-    part2(&records)?;
+    part1(&records, &mut trace)?;
+    part2(&records, &mut trace)?;
 
     part1_dp(&records)?;
     part2_dp(&records)?;
+    if let Some(explain) = explain {
+        writeln!(io::stdout(), "{}", trace.report(explain))?;
+    }
     Ok(())
 }
 
@@ -223,20 +273,30 @@ fn example_input() {
 ";
     let records = parse_input(input);
     let (s0, c0) = (vec!['#', '.', '#', '.', '#', '#', '#'], vec![1, 1, 3]);
-    assert_eq!(count_arrangement_with_unfold(&s0, &c0, 1), 1);
+    assert_eq!(count_arrangement_with_unfold(&s0, &c0, 1).unwrap(), 1);
 
     let (s1, c1) = &records[5];
-    assert_eq!(count_arrangement_with_unfold(s1, c1, 1), 10);
-    assert_eq!(part1(&records).unwrap(), 21);
-    assert_eq!(part2(&records).unwrap(), 525152);
+    assert_eq!(count_arrangement_with_unfold(s1, c1, 1).unwrap(), 10);
+    assert_eq!(part1(&records, &mut Trace::default()).unwrap(), 21);
+    assert_eq!(part2(&records, &mut Trace::default()).unwrap(), 525152);
+
+    let mut trace = Trace::new(true);
+    part2(&records[1..2], &mut trace).unwrap();
+    assert_eq!(
+        trace.report(Explain::Text),
+        "part2.record record=1 springs=.??..??...?##. count=16384"
+    );
 }
 
 #[test]
 fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
     let records = parse_input(input);
-    assert_eq!(part1(&records).unwrap(), 7694);
-    assert_eq!(part2(&records).unwrap(), 5071883216318);
+    assert_eq!(part1(&records, &mut Trace::default()).unwrap(), 7694);
+    assert_eq!(
+        part2(&records, &mut Trace::default()).unwrap(),
+        5071883216318
+    );
 }
 
 #[test]
@@ -253,6 +313,18 @@ fn example_input_dp() {
     assert_eq!(part2_dp(&records).unwrap(), 525152);
 }
 
+#[cfg(feature = "checked")]
+#[test]
+fn checked_overflow() {
+    // C(86, 43) ways to place 43 broken springs among 128 unknown ones
+    let result = count_arrangement_dp(&['?'; 128], &[1; 43]);
+    if cfg!(feature = "wide") {
+        assert_eq!(result.unwrap() as u128, 6637553085023755473070800);
+    } else {
+        assert!(result.unwrap_err().is::<checked::Overflow>());
+    }
+}
+
 #[test]
 fn real_input_dp() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
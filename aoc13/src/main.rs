@@ -3,6 +3,11 @@ use std::io::{self, Read, Write};
 use std::iter::once;
 use std::time::Instant;
 
+#[path = "../../common/explain.rs"]
+mod explain;
+
+use explain::{Explain, Trace};
+
 #[allow(unused_macros)]
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
@@ -33,7 +38,6 @@ fn parse_input<T: AsRef<str>>(input: T) -> Vec<(Vec<u32>, Vec<u32>)> {
 #[allow(dead_code)]
 fn display_note(note: &[Vec<char>]) -> String {
     note.iter()
-        .cloned()
         .map(|r| r.iter().chain(once(&'\n')).collect::<String>())
         .collect()
 }
@@ -62,7 +66,6 @@ fn search_reflection(note: &[u32], i: usize, smudge: bool) -> bool {
 }
 
 fn search_mirror(note: &(Vec<u32>, Vec<u32>), smudge: bool) -> Option<usize> {
-    println!("{:0b}", note.0[0]);
     (1..note.1.len())
         .find(|&i| search_reflection(&note.1, i, smudge))
         .or((1..note.0.len())
@@ -70,19 +73,58 @@ fn search_mirror(note: &(Vec<u32>, Vec<u32>), smudge: bool) -> Option<usize> {
             .map(|i| i * 100))
 }
 
-fn part1(notes: &[(Vec<u32>, Vec<u32>)]) -> Result<usize> {
+// a note is never 100 columns wide, so rows are the multiples of 100
+fn mirror_axis(summary: usize) -> (&'static str, usize) {
+    if summary < 100 {
+        ("column", summary)
+    } else {
+        ("row", summary / 100)
+    }
+}
+
+fn part1(notes: &[(Vec<u32>, Vec<u32>)], trace: &mut Trace) -> Result<usize> {
     let _start = Instant::now();
 
-    let result = notes.iter().map(|n| search_mirror(n, false).unwrap()).sum();
+    let result = notes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| {
+            let summary = search_mirror(n, false).unwrap();
+            trace.record("part1.note", || {
+                let (axis, index) = mirror_axis(summary);
+                vec![
+                    ("note", (i + 1).to_string()),
+                    ("axis", axis.to_string()),
+                    ("index", index.to_string()),
+                ]
+            });
+            summary
+        })
+        .sum();
 
     writeln!(io::stdout(), "Part 1:{result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", _start.elapsed())?;
     Ok(result)
 }
-fn part2(notes: &[(Vec<u32>, Vec<u32>)]) -> Result<usize> {
+fn part2(notes: &[(Vec<u32>, Vec<u32>)], trace: &mut Trace) -> Result<usize> {
     let _start = Instant::now();
 
-    let result = notes.iter().map(|n| search_mirror(n, true).unwrap()).sum();
+    let result = notes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| {
+            let summary = search_mirror(n, true).unwrap();
+            trace.record("part2.note", || {
+                let (axis, index) = mirror_axis(summary);
+                vec![
+                    ("note", (i + 1).to_string()),
+                    ("axis", axis.to_string()),
+                    ("index", index.to_string()),
+                ]
+            });
+            summary
+        })
+        .sum();
 
     writeln!(io::stdout(), "Part 2:{result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", _start.elapsed())?;
@@ -90,12 +132,18 @@ fn part2(notes: &[(Vec<u32>, Vec<u32>)]) -> Result<usize> {
 }
 
 fn main() -> Result<()> {
+    let explain = Explain::from_args()?;
+    let mut trace = Trace::new(explain.is_some());
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
     let notes = parse_input(input);
-    part1(&notes)?;
-    part2(&notes)?;
+    part1(&notes, &mut trace)?;
+    part2(&notes, &mut trace)?;
+    if let Some(explain) = explain {
+        writeln!(io::stdout(), "{}", trace.report(explain))?;
+    }
     Ok(())
 }
 
@@ -117,15 +165,21 @@ fn example_input() {
 ..##..###
 #....#..#";
     let notes = parse_input(input);
-    assert_eq!(part1(&notes).unwrap(), 405);
-    assert_eq!(part2(&notes).unwrap(), 400);
-    assert_eq!(1, 1);
+    assert_eq!(part1(&notes, &mut Trace::default()).unwrap(), 405);
+    assert_eq!(part2(&notes, &mut Trace::default()).unwrap(), 400);
+
+    let mut trace = Trace::new(true);
+    part1(&notes, &mut trace).unwrap();
+    assert_eq!(
+        trace.report(Explain::Text),
+        "part1.note note=1 axis=column index=5\npart1.note note=2 axis=row index=4"
+    );
 }
 
 #[test]
 fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
     let notes = parse_input(input);
-    assert_eq!(part1(&notes).unwrap(), 36448);
-    assert_eq!(part2(&notes).unwrap(), 35799);
+    assert_eq!(part1(&notes, &mut Trace::default()).unwrap(), 36448);
+    assert_eq!(part2(&notes, &mut Trace::default()).unwrap(), 35799);
 }
@@ -2,6 +2,13 @@ use std::error::Error;
 use std::io::{self, Read, Write};
 use std::time::Instant;
 
+#[cfg(feature = "debugger")]
+#[path = "../../common/debugger.rs"]
+mod debugger;
+
+#[cfg(feature = "debugger")]
+use debugger::{debug, Simulation};
+
 #[allow(unused_macros)]
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
@@ -9,6 +16,29 @@ macro_rules! err {
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
+#[derive(Debug, Default)]
+struct Options {
+    debug: Option<String>,
+}
+
+impl Options {
+    // `--debug <input>` steps through the tilts of an input
+    fn from_args() -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--debug" => match args.next() {
+                    Some(path) => options.debug = Some(path),
+                    None => return err!("missing the input file after --debug"),
+                },
+                _ => return err!("unknown argument {arg:?}, expect --debug <input>"),
+            }
+        }
+        Ok(options)
+    }
+}
+
 fn parse_input<T: AsRef<str>>(input: T) -> Vec<Vec<char>> {
     input
         .as_ref()
@@ -95,6 +125,50 @@ fn calc(platform: &[Vec<char>]) -> usize {
         .sum()
 }
 
+// `spin` one tilt at a step
+#[cfg(feature = "debugger")]
+struct Spin {
+    platform: Vec<Vec<char>>,
+    tilts: usize,
+}
+
+#[cfg(feature = "debugger")]
+impl Simulation for Spin {
+    fn step(&mut self) -> bool {
+        match self.tilts % 4 {
+            0 => tilt_north(&mut self.platform),
+            1 => tilt_west(&mut self.platform),
+            2 => tilt_south(&mut self.platform),
+            _ => tilt_east(&mut self.platform),
+        }
+        self.tilts += 1;
+        true
+    }
+
+    fn render(&self) -> String {
+        let next = ["north", "west", "south", "east"][self.tilts % 4];
+        let mut s = format!(
+            "cycle: {}, next tilt: {next}, load: {}\n",
+            self.tilts / 4,
+            calc(&self.platform)
+        );
+        for row in &self.platform {
+            s.extend(row);
+            s.push('\n');
+        }
+        s
+    }
+
+    fn watch(&self, name: &str) -> Option<usize> {
+        match name {
+            "load" => Some(calc(&self.platform)),
+            "cycle" => Some(self.tilts / 4),
+            "tilts" => Some(self.tilts),
+            _ => None,
+        }
+    }
+}
+
 fn part1(mut platform: Vec<Vec<char>>) -> Result<usize> {
     let _start = Instant::now();
 
@@ -144,6 +218,17 @@ fn part2(platform: Vec<Vec<char>>) -> Result<usize> {
 }
 
 fn main() -> Result<()> {
+    let options = Options::from_args()?;
+    if let Some(path) = options.debug {
+        #[cfg(feature = "debugger")]
+        {
+            let platform = parse_input(std::fs::read_to_string(path)?);
+            return debug(Spin { platform, tilts: 0 });
+        }
+        #[cfg(not(feature = "debugger"))]
+        return err!("--debug {path} needs a build with --features debugger");
+    }
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
@@ -171,6 +256,41 @@ O.#..O.#.#
     assert_eq!(part2(platform.clone()).unwrap(), 64);
 }
 
+#[cfg(feature = "debugger")]
+#[test]
+fn debug_spin() {
+    let input = "O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....";
+    let platform = parse_input(input);
+    let mut debugger = debugger::Debugger::new(Spin {
+        platform: platform.clone(),
+        tilts: 0,
+    });
+    for command in ["s", "r 2"] {
+        debugger.command(command);
+    }
+    assert!(debugger
+        .sim
+        .render()
+        .starts_with("cycle: 0, next tilt: east"));
+    for command in ["b cycle >= 3", "c"] {
+        debugger.command(command);
+    }
+    assert_eq!(debugger.message(), "breakpoint 0 hit: cycle >= 3");
+    let mut expected = platform;
+    spin(&mut expected, 3);
+    assert_eq!(debugger.sim.platform, expected);
+    assert_eq!(debugger.sim.watch("load"), Some(calc(&expected)));
+}
+
 #[test]
 fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
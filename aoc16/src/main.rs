@@ -3,6 +3,13 @@ use std::error::Error;
 use std::io::{self, Read, Write};
 use std::time::Instant;
 
+#[cfg(feature = "debugger")]
+#[path = "../../common/debugger.rs"]
+mod debugger;
+
+#[cfg(feature = "debugger")]
+use debugger::{debug, Simulation};
+
 #[allow(unused_macros)]
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
@@ -10,6 +17,29 @@ macro_rules! err {
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
+#[derive(Debug, Default)]
+struct Options {
+    debug: Option<String>,
+}
+
+impl Options {
+    // `--debug <input>` steps through the beam of an input
+    fn from_args() -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--debug" => match args.next() {
+                    Some(path) => options.debug = Some(path),
+                    None => return err!("missing the input file after --debug"),
+                },
+                _ => return err!("unknown argument {arg:?}, expect --debug <input>"),
+            }
+        }
+        Ok(options)
+    }
+}
+
 type Coord = (isize, isize);
 type Grid = Vec<Vec<char>>;
 
@@ -87,29 +117,89 @@ fn bounce(pos: Coord, dir: Direction, grid: &Grid) -> Option<(Coord, Vec<Directi
     Some((next_pos, dir.turn(tile)))
 }
 
-fn bfs(start_pos: Coord, dir: Direction, grid: &Grid) -> usize {
-    let mut queue = VecDeque::new();
+// `bfs` one beam tile at a step
+struct Beam<'a> {
+    grid: &'a Grid,
+    queue: VecDeque<(Coord, Direction)>,
+    visited: HashSet<(Coord, Direction)>,
+    energized: HashSet<Coord>,
+    steps: usize,
+}
 
-    let next_dirs = dir.turn(grid[start_pos.0 as usize][start_pos.1 as usize]);
-    for d in next_dirs {
-        queue.push_back((start_pos, d));
-    }
+impl<'a> Beam<'a> {
+    fn new(start_pos: Coord, dir: Direction, grid: &'a Grid) -> Self {
+        let mut queue = VecDeque::new();
 
-    let mut visited = HashSet::with_capacity(20_000);
+        let next_dirs = dir.turn(grid[start_pos.0 as usize][start_pos.1 as usize]);
+        for d in next_dirs {
+            queue.push_back((start_pos, d));
+        }
 
-    let mut energized = HashSet::with_capacity(10_000);
+        Beam {
+            grid,
+            queue,
+            visited: HashSet::with_capacity(20_000),
+            energized: HashSet::with_capacity(10_000),
+            steps: 0,
+        }
+    }
 
-    while let Some((pos, dir)) = queue.pop_front() {
-        energized.insert(pos);
-        if let Some((next, dirs)) = bounce(pos, dir, grid) {
+    fn step(&mut self) -> bool {
+        let Some((pos, dir)) = self.queue.pop_front() else {
+            return false;
+        };
+        self.energized.insert(pos);
+        if let Some((next, dirs)) = bounce(pos, dir, self.grid) {
             for n_d in dirs {
-                if visited.insert((next, n_d)) {
-                    queue.push_back((next, n_d));
+                if self.visited.insert((next, n_d)) {
+                    self.queue.push_back((next, n_d));
                 }
             }
         }
+        self.steps += 1;
+        true
     }
-    energized.len()
+}
+
+#[cfg(feature = "debugger")]
+impl Simulation for Beam<'_> {
+    fn step(&mut self) -> bool {
+        Beam::step(self)
+    }
+
+    fn render(&self) -> String {
+        let mut s = format!(
+            "queue: {}, energized: {}\n",
+            self.queue.len(),
+            self.energized.len()
+        );
+        for (x, row) in self.grid.iter().enumerate() {
+            for (y, &tile) in row.iter().enumerate() {
+                if tile == '.' && self.energized.contains(&(x as isize, y as isize)) {
+                    s.push('#');
+                } else {
+                    s.push(tile);
+                }
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    fn watch(&self, name: &str) -> Option<usize> {
+        match name {
+            "queue" => Some(self.queue.len()),
+            "energized" => Some(self.energized.len()),
+            "steps" => Some(self.steps),
+            _ => None,
+        }
+    }
+}
+
+fn bfs(start_pos: Coord, dir: Direction, grid: &Grid) -> usize {
+    let mut beam = Beam::new(start_pos, dir, grid);
+    while beam.step() {}
+    beam.energized.len()
 }
 
 fn part1(grid: &Grid) -> Result<usize> {
@@ -149,6 +239,17 @@ fn part2(grid: &Grid) -> Result<usize> {
     Ok(result)
 }
 fn main() -> Result<()> {
+    let options = Options::from_args()?;
+    if let Some(path) = options.debug {
+        #[cfg(feature = "debugger")]
+        {
+            let grid = parse_input(std::fs::read_to_string(path)?);
+            return debug(Beam::new((0, 0), Direction::Right, &grid));
+        }
+        #[cfg(not(feature = "debugger"))]
+        return err!("--debug {path} needs a build with --features debugger");
+    }
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
@@ -175,6 +276,39 @@ fn example_input() {
     assert_eq!(part2(&grid).unwrap(), 51);
 }
 
+#[cfg(feature = "debugger")]
+#[test]
+fn debug_beam() {
+    let input = r#".|...\....
+|.-.\.....
+.....|-...
+........|.
+..........
+.........\
+..../.\\..
+.-.-/..|..
+.|....-|.\
+..//.|...."#;
+    let grid = parse_input(input);
+    let mut debugger = debugger::Debugger::new(Beam::new((0, 0), Direction::Right, &grid));
+    for command in ["b energized >= 10", "c"] {
+        debugger.command(command);
+    }
+    assert_eq!(debugger.message(), "breakpoint 0 hit: energized >= 10");
+    // without a breakpoint `c` refuses to run, a long enough `r` runs the beam out
+    for command in ["d", "c"] {
+        debugger.command(command);
+    }
+    assert!(!debugger.finished());
+    debugger.command("r 1000");
+    assert!(debugger.finished());
+    assert!(debugger
+        .sim
+        .render()
+        .starts_with("queue: 0, energized: 46\n"));
+    assert_eq!(debugger.sim.watch("energized"), Some(46));
+}
+
 #[test]
 fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
@@ -11,7 +11,18 @@ macro_rules! err {
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
-type Coord = (isize, isize);
+// the integer type of the hot arithmetic, `--features wide` switches it to 128 bits
+#[cfg(not(feature = "wide"))]
+type Int = isize;
+#[cfg(feature = "wide")]
+type Int = i128;
+
+#[path = "../../common/checked.rs"]
+mod checked;
+
+use checked::{add, mul, sub};
+
+type Coord = (Int, Int);
 type Direction = char;
 
 #[derive(Debug)]
@@ -97,42 +108,46 @@ fn display_grid(grid: &HashSet<Coord>) -> String {
     s
 }
 
-fn dig_edge(pos: Coord, plan: &Plan, grid: &mut HashSet<Coord>) -> Coord {
+fn dig_edge(pos: Coord, plan: &Plan, grid: &mut HashSet<Coord>) -> Result<Coord> {
     let (x, y) = pos;
-    let step = plan.step;
-    match plan.dir {
+    let step = plan.step as Int;
+    Ok(match plan.dir {
         'U' => {
-            grid.extend((x - step..x).map(|i| (i, y)));
-            (x - step, y)
+            let to = sub(x, step)?;
+            grid.extend((to..x).map(|i| (i, y)));
+            (to, y)
         }
         'D' => {
-            grid.extend((x + 1..=x + step).map(|i| (i, y)));
-            (x + step, y)
+            let to = add(x, step)?;
+            grid.extend((x + 1..=to).map(|i| (i, y)));
+            (to, y)
         }
         'L' => {
-            grid.extend((y - step..y).map(|j| (x, j)));
-            (x, y - step)
+            let to = sub(y, step)?;
+            grid.extend((to..y).map(|j| (x, j)));
+            (x, to)
         }
         'R' => {
-            grid.extend((y + 1..=y + step).map(|j| (x, j)));
-            (x, y + step)
+            let to = add(y, step)?;
+            grid.extend((y + 1..=to).map(|j| (x, j)));
+            (x, to)
         }
         _ => unreachable!("Wrong direction: {plan:?}"),
-    }
+    })
 }
 
-fn dig_trench(plans: &[Plan]) -> HashSet<Coord> {
+fn dig_trench(plans: &[Plan]) -> Result<HashSet<Coord>> {
     let mut grid = HashSet::new();
 
     let mut curr = (0, 0);
     for plan in plans {
-        curr = dig_edge(curr, plan, &mut grid);
+        curr = dig_edge(curr, plan, &mut grid)?;
     }
 
-    grid
+    Ok(grid)
 }
 
-fn ray_cast(grid: &HashSet<Coord>) -> usize {
+fn ray_cast(grid: &HashSet<Coord>) -> Result<Int> {
     let min_x = grid.iter().min().unwrap().0;
     let max_x = grid.iter().max().unwrap().0;
     let min_y = grid.iter().min_by_key(|k| k.1).unwrap().1;
@@ -144,7 +159,7 @@ fn ray_cast(grid: &HashSet<Coord>) -> usize {
         for y in min_y..=max_y {
             if !grid.contains(&(x, y)) {
                 if count % 2 == 1 {
-                    total_count += 1;
+                    total_count = add(total_count, 1)?;
                 }
             } else if (grid.contains(&(x + 1, y)) && grid.contains(&(x, y + 1)))
                 || (grid.contains(&(x, y - 1)) && grid.contains(&(x + 1, y)))
@@ -154,22 +169,22 @@ fn ray_cast(grid: &HashSet<Coord>) -> usize {
             }
         }
     }
-    total_count + grid.len()
+    add(total_count, grid.len() as Int)
 }
 
-fn part1(plans: &[Plan]) -> Result<usize> {
+fn part1(plans: &[Plan]) -> Result<Int> {
     let _start = Instant::now();
 
-    let grid = dig_trench(plans);
+    let grid = dig_trench(plans)?;
 
-    let result = ray_cast(&grid);
+    let result = ray_cast(&grid)?;
 
     writeln!(io::stdout(), "Part 1: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", _start.elapsed())?;
     Ok(result)
 }
 
-fn part2(plans: &[Plan]) -> Result<usize> {
+fn part2(plans: &[Plan]) -> Result<Int> {
     let _start = Instant::now();
 
     let plans = plans
@@ -177,24 +192,30 @@ fn part2(plans: &[Plan]) -> Result<usize> {
         .map(|p| Plan::from_rgb(p.rgb.as_ref().unwrap()))
         .collect::<Result<Vec<_>>>()?;
 
-    let mut interior = 0;
+    let mut interior: Int = 0;
     let mut edge = 0;
     let mut curr = (0, 0);
     for plan in &plans {
         let (x, y) = curr;
-        let step = plan.step;
+        let step = plan.step as Int;
         let next = match plan.dir {
-            'U' => (x - step, y),
-            'D' => (x + step, y),
-            'L' => (x, y - step),
-            'R' => (x, y + step),
+            'U' => (sub(x, step)?, y),
+            'D' => (add(x, step)?, y),
+            'L' => (x, sub(y, step)?),
+            'R' => (x, add(y, step)?),
             _ => unreachable!(),
         };
-        edge += step;
-        interior += x * next.1 - next.0 * y;
+        edge = add(edge, step)?;
+        interior = add(interior, sub(mul(x, next.1)?, mul(next.0, y)?)?)?;
         curr = next;
     }
-    let result = ((edge + interior.abs()) / 2 + 1).unsigned_abs();
+    // |interior| through `sub`, so Int::MIN is an overflow as well
+    let area = if interior < 0 {
+        sub(0, interior)?
+    } else {
+        interior
+    };
+    let result = add(edge, area)? / 2 + 1;
 
     writeln!(io::stdout(), "Part 2: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", _start.elapsed())?;
@@ -233,6 +254,21 @@ U 2 (#7a21e3)";
     assert_eq!(part2(&plans).unwrap(), 952408144115);
 }
 
+#[cfg(feature = "checked")]
+#[test]
+fn checked_overflow() {
+    // a square with sides of 2^34 has twice its area beyond 2^64
+    let plans =
+        parse_input("R 1 (#4000000000)\nD 1 (#4000000001)\nL 1 (#4000000002)\nU 1 (#4000000003)")
+            .unwrap();
+    let result = part2(&plans);
+    if cfg!(feature = "wide") {
+        assert_eq!(result.unwrap() as i128, (1 << 68) + (1 << 35) + 1);
+    } else {
+        assert!(result.unwrap_err().is::<checked::Overflow>());
+    }
+}
+
 #[test]
 fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
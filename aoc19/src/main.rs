@@ -3,6 +3,11 @@ use std::error::Error;
 use std::io::{self, Read, Write};
 use std::time::Instant;
 
+#[path = "../../common/explain.rs"]
+mod explain;
+
+use explain::{Explain, Trace};
+
 #[allow(unused_macros)]
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
@@ -10,6 +15,17 @@ macro_rules! err {
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
+// the integer type of the hot arithmetic, `--features wide` switches it to 128 bits
+#[cfg(not(feature = "wide"))]
+type Int = usize;
+#[cfg(feature = "wide")]
+type Int = u128;
+
+#[path = "../../common/checked.rs"]
+mod checked;
+
+use checked::{add, mul};
+
 type WorkflowIdMap = HashMap<usize, Workflow>;
 type WorkflowNameMap = HashMap<String, usize>;
 
@@ -185,6 +201,16 @@ fn parse_input<T: AsRef<str>>(
     err!("unable to parse input")
 }
 
+fn rating_name(rating: char) -> &'static str {
+    match rating {
+        'x' => "x",
+        'm' => "m",
+        'a' => "a",
+        's' => "s",
+        _ => "?",
+    }
+}
+
 fn process(rating: &[(char, usize)], ws: &WorkflowIdMap, map: &WorkflowNameMap) -> bool {
     let mut curr_wf = ws.get(map.get("in").unwrap()).unwrap();
 
@@ -203,12 +229,24 @@ fn part1(
     rs: &[Vec<(char, usize)>],
     ws: &HashMap<usize, Workflow>,
     map: &HashMap<String, usize>,
+    trace: &mut Trace,
 ) -> Result<usize> {
     let _start = Instant::now();
 
     let result = rs
         .iter()
-        .filter(|r| process(r, ws, map))
+        .filter(|r| {
+            let accepted = process(r, ws, map);
+            trace.record("part1.part", || {
+                let mut fields: Vec<_> = r
+                    .iter()
+                    .map(|&(c, v)| (rating_name(c), v.to_string()))
+                    .collect();
+                fields.push(("accepted", accepted.to_string()));
+                fields
+            });
+            accepted
+        })
         .map(|r| r.iter().map(|(_, v)| v).sum::<usize>())
         .sum();
 
@@ -277,13 +315,29 @@ impl Possible {
         (left, right)
     }
 
-    fn count(&self) -> usize {
-        let (x, m, a, s) = (self.x, self.m, self.a, self.s);
-        (x.1 - x.0 + 1) * (m.1 - m.0 + 1) * (a.1 - a.0 + 1) * (s.1 - s.0 + 1)
+    // count the accepted combinations, recorded as one hyper-rectangle of the trace
+    fn accept(&self, trace: &mut Trace) -> Result<Int> {
+        let count = self.count()?;
+        trace.record("part2.accepted", || {
+            vec![
+                ("x", format!("{}..={}", self.x.0, self.x.1)),
+                ("m", format!("{}..={}", self.m.0, self.m.1)),
+                ("a", format!("{}..={}", self.a.0, self.a.1)),
+                ("s", format!("{}..={}", self.s.0, self.s.1)),
+                ("count", count.to_string()),
+            ]
+        });
+        Ok(count)
+    }
+
+    fn count(&self) -> Result<Int> {
+        [self.x, self.m, self.a, self.s]
+            .iter()
+            .try_fold(1, |count, r| mul(count, (r.1 - r.0 + 1) as Int))
     }
 }
 
-fn dp(id: usize, ws: &WorkflowIdMap, possible: Possible) -> usize {
+fn dp(id: usize, ws: &WorkflowIdMap, possible: Possible, trace: &mut Trace) -> Result<Int> {
     let mut result = 0;
     let wf = ws.get(&id).unwrap();
     let l = wf.rules.len();
@@ -293,45 +347,53 @@ fn dp(id: usize, ws: &WorkflowIdMap, possible: Possible) -> usize {
             let (occupy, remain) = possible.split(rating, op, op1);
             if let Some(occupy) = occupy {
                 match rule.result {
-                    ProcessingResult::Accepted => result += occupy.count(),
+                    ProcessingResult::Accepted => result = add(result, occupy.accept(trace)?)?,
                     ProcessingResult::Rejected => (),
-                    ProcessingResult::Workflow(n_id) => result += dp(n_id, ws, occupy),
+                    ProcessingResult::Workflow(n_id) => {
+                        result = add(result, dp(n_id, ws, occupy, trace)?)?
+                    }
                 }
             }
             if let Some(remain) = remain {
                 possible = remain;
             } else {
-                return result;
+                return Ok(result);
             }
         }
     }
     match wf.rules[l - 1].result {
-        ProcessingResult::Accepted => result += possible.count(),
+        ProcessingResult::Accepted => result = add(result, possible.accept(trace)?)?,
         ProcessingResult::Rejected => (),
-        ProcessingResult::Workflow(n_id) => result += dp(n_id, ws, possible),
+        ProcessingResult::Workflow(n_id) => result = add(result, dp(n_id, ws, possible, trace)?)?,
     }
 
-    result
+    Ok(result)
 }
 
-fn part2(ws: &WorkflowIdMap, map: &WorkflowNameMap) -> Result<usize> {
+fn part2(ws: &WorkflowIdMap, map: &WorkflowNameMap, trace: &mut Trace) -> Result<Int> {
     let _start = Instant::now();
 
     let &id = map.get("in").unwrap();
 
-    let result = dp(id, ws, Possible::new());
+    let result = dp(id, ws, Possible::new(), trace)?;
 
     writeln!(io::stdout(), "Part 2: {result}")?;
     writeln!(io::stdout(), "> Time elapsed is: {:?}", _start.elapsed())?;
     Ok(result)
 }
 fn main() -> Result<()> {
+    let explain = Explain::from_args()?;
+    let mut trace = Trace::new(explain.is_some());
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
     let (rs, ws, map) = parse_input(input)?;
-    part1(&rs, &ws, &map)?;
-    part2(&ws, &map)?;
+    part1(&rs, &ws, &map, &mut trace)?;
+    part2(&ws, &map, &mut trace)?;
+    if let Some(explain) = explain {
+        writeln!(io::stdout(), "{}", trace.report(explain))?;
+    }
     Ok(())
 }
 
@@ -356,14 +418,61 @@ hdj{m>838:A,pv}
 {x=2127,m=1623,a=2188,s=1013}";
 
     let (rs, ws, map) = parse_input(input).unwrap();
-    assert_eq!(part1(&rs, &ws, &map).unwrap(), 19114);
-    assert_eq!(part2(&ws, &map).unwrap(), 167409079868000);
+    assert_eq!(part1(&rs, &ws, &map, &mut Trace::default()).unwrap(), 19114);
+    assert_eq!(
+        part2(&ws, &map, &mut Trace::default()).unwrap(),
+        167409079868000
+    );
+
+    let mut trace = Trace::new(true);
+    part1(&rs[..1], &ws, &map, &mut trace).unwrap();
+    assert_eq!(
+        trace.report(Explain::Text),
+        "part1.part x=787 m=2655 a=1222 s=2876 accepted=true"
+    );
+    let mut trace = Trace::new(true);
+    let total = part2(&ws, &map, &mut trace).unwrap();
+    assert_eq!(
+        trace
+            .report(Explain::Text)
+            .lines()
+            .map(|l| l.rsplit_once('=').unwrap().1.parse::<Int>().unwrap())
+            .sum::<Int>(),
+        total
+    );
+}
+
+#[cfg(feature = "checked")]
+#[test]
+fn checked_overflow() {
+    assert_eq!(Possible::new().count().unwrap(), 256000000000000);
+    // four ratings of 2^16 values each have 2^64 combinations
+    let r = (1, 1 << 16);
+    let possible = Possible {
+        x: r,
+        m: r,
+        a: r,
+        s: r,
+    };
+    let result = possible.count();
+    if cfg!(feature = "wide") {
+        // compared as text, the cast would be a no-op with `wide`
+        assert_eq!(result.unwrap().to_string(), (1u128 << 64).to_string());
+    } else {
+        assert!(result.unwrap_err().is::<checked::Overflow>());
+    }
 }
 
 #[test]
 fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
     let (rs, ws, map) = parse_input(input).unwrap();
-    assert_eq!(part1(&rs, &ws, &map).unwrap(), 391132);
-    assert_eq!(part2(&ws, &map).unwrap(), 128163929109524);
+    assert_eq!(
+        part1(&rs, &ws, &map, &mut Trace::default()).unwrap(),
+        391132
+    );
+    assert_eq!(
+        part2(&ws, &map, &mut Trace::default()).unwrap(),
+        128163929109524
+    );
 }
@@ -4,6 +4,18 @@ use std::io::{self, Read, Write};
 use std::str::FromStr;
 use std::time::Instant;
 
+#[path = "../../common/explain.rs"]
+mod explain;
+
+use explain::{Explain, Trace};
+
+#[cfg(feature = "debugger")]
+#[path = "../../common/debugger.rs"]
+mod debugger;
+
+#[cfg(feature = "debugger")]
+use debugger::{debug, Simulation};
+
 #[allow(unused_macros)]
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
@@ -11,6 +23,38 @@ macro_rules! err {
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
+#[derive(Debug, Default)]
+struct Options {
+    explain: Option<Explain>,
+    debug: Option<String>,
+}
+
+impl Options {
+    // `--explain` prints the trace as text, `--explain=json` as json,
+    // `--debug <input>` steps through the pulses of an input
+    fn from_args() -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--debug" => match args.next() {
+                    Some(path) => options.debug = Some(path),
+                    None => return err!("missing the input file after --debug"),
+                },
+                _ => match Explain::from_arg(&arg) {
+                    Some(explain) => options.explain = Some(explain),
+                    None => {
+                        return err!(
+                        "unknown argument {arg:?}, expect --explain[=text|json] or --debug <input>"
+                    )
+                    }
+                },
+            }
+        }
+        Ok(options)
+    }
+}
+
 #[derive(Debug, Clone)]
 enum Module {
     FlipFlop(String, bool),
@@ -95,60 +139,176 @@ impl FromStr for Machine {
     }
 }
 
+// (sender, receiver, pulse), a high pulse is true
+type Pulse = (String, String, bool);
+
 impl Machine {
-    fn push_button(&mut self, check_on: Option<&str>) -> (usize, usize, bool) {
+    // deliver one pulse and queue the pulses it sends, the inputs of `check_on` that it
+    // remembers as high after the pulse
+    fn receive(
+        &mut self,
+        pulse: &Pulse,
+        check_on: Option<&str>,
+        queue: &mut VecDeque<Pulse>,
+    ) -> Vec<String> {
+        let (sender, receiver, pulse) = pulse;
+        let mut send = |next_pulse: bool| {
+            for next_module in self.cables.get(receiver).into_iter().flatten() {
+                queue.push_back((receiver.clone(), next_module.clone(), next_pulse));
+            }
+        };
+
+        let mut high = vec![];
+        let module = if let Some(module) = self.modules.get_mut(receiver) {
+            module
+        } else {
+            return high;
+        };
+        match module {
+            Module::FlipFlop(_, status) => {
+                if !pulse {
+                    *status = !*status;
+                    send(*status);
+                }
+            }
+            Module::Conjunction(_, status) => {
+                status.insert(sender.clone(), *pulse);
+                let next_pulse = !status.values().all(|b| *b);
+                if Some(receiver.as_str()) == check_on {
+                    high.extend(status.iter().filter(|(_, v)| **v).map(|(s, _)| s.clone()));
+                }
+                send(next_pulse);
+            }
+            Module::Broadcaster => send(*pulse),
+            Module::Button => queue.push_back(Machine::button()),
+        }
+        high
+    }
+
+    fn button() -> Pulse {
+        ("button".to_string(), "broadcaster".to_string(), false)
+    }
+
+    fn push_button(&mut self, check_on: Option<&str>) -> (usize, usize, Vec<String>) {
         let mut queue = VecDeque::new();
-        queue.push_back(("button", "broadcaster", false));
+        queue.push_back(Machine::button());
 
         let mut low_cnt = 0;
         let mut high_cnt = 0;
 
-        let mut flag = false;
+        let mut high = vec![];
+
+        while let Some(pulse) = queue.pop_front() {
+            low_cnt += (!pulse.2) as usize;
+            high_cnt += pulse.2 as usize;
+            high.extend(self.receive(&pulse, check_on, &mut queue));
+        }
 
-        while let Some((sender, receiver, pulse)) = queue.pop_front() {
-            low_cnt += (!pulse) as usize;
-            high_cnt += pulse as usize;
-            // println!("{sender} - {pulse:?} -> {receiver}");
+        (low_cnt, high_cnt, high)
+    }
+}
+
+// `push_button` one pulse at a step, pushing the button again once the pulses settle
+#[cfg(feature = "debugger")]
+struct Press {
+    machine: Machine,
+    queue: VecDeque<Pulse>,
+    presses: usize,
+    low_cnt: usize,
+    high_cnt: usize,
+    last: Option<Pulse>,
+    steps: usize,
+}
+
+#[cfg(feature = "debugger")]
+impl Press {
+    fn new(machine: Machine) -> Self {
+        Press {
+            machine,
+            queue: VecDeque::new(),
+            presses: 0,
+            low_cnt: 0,
+            high_cnt: 0,
+            last: None,
+            steps: 0,
+        }
+    }
+}
 
-            let module = if let Some(module) = self.modules.get_mut(receiver) {
-                module
-            } else {
-                continue;
-            };
-            let dest = self.cables.get(receiver).unwrap();
+#[cfg(feature = "debugger")]
+impl Simulation for Press {
+    fn step(&mut self) -> bool {
+        if self.queue.is_empty() {
+            self.queue.push_back(Machine::button());
+            self.presses += 1;
+        }
+        let pulse = self.queue.pop_front().unwrap();
+        self.low_cnt += (!pulse.2) as usize;
+        self.high_cnt += pulse.2 as usize;
+        self.machine.receive(&pulse, None, &mut self.queue);
+        self.last = Some(pulse);
+        self.steps += 1;
+        true
+    }
+
+    fn render(&self) -> String {
+        let show = |(sender, receiver, pulse): &Pulse| {
+            format!(
+                "{sender} -{}-> {receiver}",
+                if *pulse { "high" } else { "low" }
+            )
+        };
+        let mut s = format!(
+            "presses: {}, low: {}, high: {}\nlast: {}\n",
+            self.presses,
+            self.low_cnt,
+            self.high_cnt,
+            self.last.as_ref().map(show).unwrap_or_default()
+        );
+        for pulse in &self.queue {
+            s += &format!("  {}\n", show(pulse));
+        }
+        let mut modules: Vec<_> = self.machine.modules.values().collect();
+        modules.sort_by_key(|m| m.name());
+        for module in modules {
             match module {
-                Module::FlipFlop(_, status) => {
-                    if !pulse {
-                        *status = !*status;
-                        let next_pulse = *status;
-                        for next_module in dest {
-                            queue.push_back((receiver, next_module, next_pulse));
-                        }
-                    }
-                }
-                Module::Conjunction(_, status) => {
-                    status.insert(sender.to_string(), pulse);
-                    let next_pulse = !status.values().all(|b| *b);
-                    if Some(receiver) == check_on {
-                        for s in status.iter().filter(|(_, v)| **v) {
-                            flag = true;
-                            println!("vd: {s:?}");
-                        }
-                    }
-                    for next_module in dest {
-                        queue.push_back((receiver, next_module, next_pulse));
+                Module::FlipFlop(name, on) => s += &format!("%{name}: {}\n", *on as u8),
+                Module::Conjunction(name, status) => {
+                    let mut inputs: Vec<_> = status.iter().collect();
+                    inputs.sort();
+                    s += &format!("&{name}:");
+                    for (input, high) in inputs {
+                        s += &format!(" {input}={}", *high as u8);
                     }
+                    s.push('\n');
                 }
-                Module::Broadcaster => {
-                    for next_module in dest {
-                        queue.push_back((receiver, next_module, pulse));
-                    }
-                }
-                Module::Button => queue.push_back(("button", "broadcaster", false)),
+                _ => {}
             }
         }
+        s
+    }
 
-        (low_cnt, high_cnt, flag)
+    // `high->NAME` and `low->NAME` are 1 while the last pulse is of that kind into NAME
+    fn watch(&self, name: &str) -> Option<usize> {
+        let last = |high: bool, receiver: &str| {
+            self.last
+                .as_ref()
+                .map_or(0, |(_, r, p)| (*p == high && r == receiver) as usize)
+        };
+        match name {
+            "presses" => Some(self.presses),
+            "queue" => Some(self.queue.len()),
+            "low" => Some(self.low_cnt),
+            "high" => Some(self.high_cnt),
+            "steps" => Some(self.steps),
+            _ => {
+                if let Some(receiver) = name.strip_prefix("high->") {
+                    Some(last(true, receiver))
+                } else {
+                    name.strip_prefix("low->").map(|r| last(false, r))
+                }
+            }
+        }
     }
 }
 
@@ -171,7 +331,7 @@ fn part1(machine: &Machine) -> Result<usize> {
     Ok(result)
 }
 
-fn part2(machine: &Machine) -> Result<usize> {
+fn part2(machine: &Machine, trace: &mut Trace) -> Result<usize> {
     let _start = Instant::now();
 
     let mut machine = machine.clone();
@@ -187,10 +347,13 @@ fn part2(machine: &Machine) -> Result<usize> {
     }
     let rx_rely = rx_rely[0].clone();
 
+    // the presses where an input of the module before rx is high, their lcm is the answer
     for i in 1..10000 {
-        let (_, _, b) = machine.push_button(Some(&rx_rely));
-        if b {
-            println!("{i}");
+        let (_, _, high) = machine.push_button(Some(&rx_rely));
+        for input in high {
+            trace.record("part2.high", || {
+                vec![("press", i.to_string()), ("input", input)]
+            });
         }
     }
 
@@ -202,13 +365,28 @@ fn part2(machine: &Machine) -> Result<usize> {
 }
 
 fn main() -> Result<()> {
+    let options = Options::from_args()?;
+    if let Some(path) = options.debug {
+        #[cfg(feature = "debugger")]
+        {
+            let machine = std::fs::read_to_string(path)?.parse::<Machine>()?;
+            return debug(Press::new(machine));
+        }
+        #[cfg(not(feature = "debugger"))]
+        return err!("--debug {path} needs a build with --features debugger");
+    }
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
     let machine = input.parse::<Machine>()?;
 
+    let mut trace = Trace::new(options.explain.is_some());
     part1(&machine)?;
-    part2(&machine)?;
+    part2(&machine, &mut trace)?;
+    if let Some(explain) = options.explain {
+        writeln!(io::stdout(), "{}", trace.report(explain))?;
+    }
     Ok(())
 }
 
@@ -231,6 +409,44 @@ fn example_input() {
     assert_eq!(part1(&machine).unwrap(), 11687500);
 }
 
+#[cfg(feature = "debugger")]
+#[test]
+fn debug_press() {
+    let input = "broadcaster -> a
+%a -> inv, con
+&inv -> b
+%b -> con
+&con -> output";
+    let machine = input.parse::<Machine>().unwrap();
+    let mut debugger = debugger::Debugger::new(Press::new(machine));
+    for command in ["b high->output", "c"] {
+        debugger.command(command);
+    }
+    assert_eq!(debugger.message(), "breakpoint 0 hit: high->output != 0");
+    assert!(debugger.sim.render().contains("last: con -high-> output\n"));
+    assert_eq!(debugger.sim.watch("presses"), Some(1));
+    assert_eq!(debugger.sim.watch("high->output"), Some(1));
+}
+
+#[test]
+fn explain_trace() {
+    let input = "broadcaster -> a
+%a -> inv
+&inv -> hub
+&hub -> rx";
+    let machine = input.parse::<Machine>().unwrap();
+    let mut trace = Trace::new(true);
+    part2(&machine, &mut trace).unwrap();
+    let report = trace.report(Explain::Text);
+    assert_eq!(
+        report.lines().take(2).collect::<Vec<_>>(),
+        [
+            "part2.high press=2 input=inv",
+            "part2.high press=4 input=inv"
+        ]
+    );
+}
+
 #[test]
 fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
@@ -3,6 +3,13 @@ use std::error::Error;
 use std::io::{self, Read, Write};
 use std::time::Instant;
 
+#[cfg(feature = "debugger")]
+#[path = "../../common/debugger.rs"]
+mod debugger;
+
+#[cfg(feature = "debugger")]
+use debugger::{debug, Simulation};
+
 #[allow(unused_macros)]
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
@@ -10,6 +17,33 @@ macro_rules! err {
 
 type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
 
+#[derive(Debug, Default)]
+struct Options {
+    debug: Option<String>,
+    limit: Option<usize>,
+}
+
+impl Options {
+    // `--debug <input> [steps]` steps through an input up to 64 steps by default
+    fn from_args() -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--debug" => match args.next() {
+                    Some(path) => options.debug = Some(path),
+                    None => return err!("missing the input file after --debug"),
+                },
+                steps if options.debug.is_some() && options.limit.is_none() => {
+                    options.limit = Some(steps.parse()?)
+                }
+                _ => return err!("unknown argument {arg:?}, expect --debug <input> [steps]"),
+            }
+        }
+        Ok(options)
+    }
+}
+
 type Coord = (isize, isize);
 
 fn parse_input<T: AsRef<str>>(input: T) -> (Coord, Coord, HashSet<Coord>) {
@@ -37,26 +71,93 @@ fn normalize_coord(pos: Coord, bound: Coord) -> (Coord, Coord) {
     (origin, dis)
 }
 
-fn bfs(start: Coord, step: usize, bound: Coord, map: &HashSet<Coord>) -> usize {
-    let mut visited = HashSet::new();
-    visited.insert(start);
-    let mut count = 0;
-    let mut f = [0, 0, 0];
+// `bfs` one step of the elf at a step, until `limit` steps are taken
+struct Garden<'a> {
+    bound: Coord,
+    map: &'a HashSet<Coord>,
+    frontier: HashSet<Coord>,
+    steps: usize,
+    limit: usize,
+}
 
-    for i in 1..=step {
-        let mut temp = HashSet::with_capacity(visited.len() * 3);
-        for curr in visited {
+impl<'a> Garden<'a> {
+    fn new(start: Coord, limit: usize, bound: Coord, map: &'a HashSet<Coord>) -> Self {
+        Garden {
+            bound,
+            map,
+            frontier: HashSet::from([start]),
+            steps: 0,
+            limit,
+        }
+    }
+
+    fn step(&mut self) -> bool {
+        if self.steps >= self.limit {
+            return false;
+        }
+        let mut temp = HashSet::with_capacity(self.frontier.len() * 3);
+        for curr in &self.frontier {
             for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
                 let (nx, ny) = (curr.0 + dx, curr.1 + dy);
-                let (origin, _) = normalize_coord((nx, ny), bound);
-                if map.contains(&origin) {
+                let (origin, _) = normalize_coord((nx, ny), self.bound);
+                if self.map.contains(&origin) {
                     temp.insert((nx, ny));
                 }
             }
         }
-        visited = temp;
+        self.frontier = temp;
+        self.steps += 1;
+        true
+    }
+}
+
+#[cfg(feature = "debugger")]
+impl Simulation for Garden<'_> {
+    fn step(&mut self) -> bool {
+        Garden::step(self)
+    }
+
+    // only the starting tile of the infinite garden is drawn
+    fn render(&self) -> String {
+        let mut s = format!(
+            "steps: {}/{}, frontier: {}\n",
+            self.steps,
+            self.limit,
+            self.frontier.len()
+        );
+        for x in 0..self.bound.0 {
+            for y in 0..self.bound.1 {
+                if self.frontier.contains(&(x, y)) {
+                    s.push('O');
+                } else if self.map.contains(&(x, y)) {
+                    s.push('.');
+                } else {
+                    s.push('#');
+                }
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    fn watch(&self, name: &str) -> Option<usize> {
+        match name {
+            "frontier" => Some(self.frontier.len()),
+            "steps" => Some(self.steps),
+            _ => None,
+        }
+    }
+}
+
+fn bfs(start: Coord, step: usize, bound: Coord, map: &HashSet<Coord>) -> usize {
+    let mut garden = Garden::new(start, step, bound, map);
+    let mut count = 0;
+    let mut f = [0, 0, 0];
+
+    for i in 1..=step {
+        garden.step();
         if (step as isize) % bound.0 == (i as isize) % bound.0 {
-            f[count] = visited.len();
+            f[count] = garden.frontier.len();
             // let x = i as isize / bound.0;
             // ax^2 + bx + c = visited.len()
             count += 1;
@@ -72,7 +173,7 @@ fn bfs(start: Coord, step: usize, bound: Coord, map: &HashSet<Coord>) -> usize {
         }
     }
 
-    visited.len()
+    garden.frontier.len()
 }
 
 fn part1(start: Coord, bound: Coord, map: &HashSet<Coord>) -> Result<usize> {
@@ -96,6 +197,17 @@ fn part2(start: Coord, bound: Coord, map: &HashSet<Coord>) -> Result<usize> {
 }
 
 fn main() -> Result<()> {
+    let options = Options::from_args()?;
+    if let Some(path) = options.debug {
+        #[cfg(feature = "debugger")]
+        {
+            let (start, bound, map) = parse_input(std::fs::read_to_string(path)?);
+            return debug(Garden::new(start, options.limit.unwrap_or(64), bound, &map));
+        }
+        #[cfg(not(feature = "debugger"))]
+        return err!("--debug {path} needs a build with --features debugger");
+    }
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
@@ -128,6 +240,40 @@ fn example_input() {
     assert_eq!(bfs(start, 5000, bound, &map), 16733044);
 }
 
+#[cfg(feature = "debugger")]
+#[test]
+fn debug_garden() {
+    let input = "...........
+.....###.#.
+.###.##..#.
+..#.#...#..
+....#.#....
+.##..S####.
+.##..#...#.
+.......##..
+.##.#.####.
+.##..##.##.
+...........";
+    let (start, bound, map) = parse_input(input);
+    let mut debugger = debugger::Debugger::new(Garden::new(start, 6, bound, &map));
+    for command in ["b frontier > 10", "c"] {
+        debugger.command(command);
+    }
+    assert_eq!(debugger.steps(), 5);
+    assert!(debugger
+        .sim
+        .render()
+        .starts_with("steps: 5/6, frontier: 13\n"));
+    for command in ["c", "c"] {
+        debugger.command(command);
+    }
+    assert!(debugger.finished());
+    assert!(debugger
+        .sim
+        .render()
+        .starts_with("steps: 6/6, frontier: 16\n"));
+}
+
 #[test]
 fn real_input() {
     let input = std::fs::read_to_string("input/input.txt").unwrap();
@@ -7,6 +7,11 @@ use z3::{
     Solver,
 };
 
+#[path = "../../common/explain.rs"]
+mod explain;
+
+use explain::{Explain, Trace};
+
 #[allow(unused_macros)]
 macro_rules! err {
     ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
@@ -118,7 +123,7 @@ fn solve_with_z3(stones: &[Hailstone]) -> isize {
     r as isize
 }
 
-fn part2(stones: &[Hailstone]) -> Result<isize> {
+fn part2(stones: &[Hailstone], trace: &mut Trace) -> Result<isize> {
     let _start = Instant::now();
 
     // rock (x, y, z) (vx, vy, vz)
@@ -135,13 +140,16 @@ fn part2(stones: &[Hailstone]) -> Result<isize> {
     // 6 + x = 3 * x
     // x = 3
     for (i, s) in stones[..3].iter().enumerate() {
-        let p = s.position;
-        let v = s.velocity;
-        let (x, y, z) = p;
-        let (vx, vy, vz) = v;
-        println!("x+vx*t{i}={}+{}*t{i},", x, vx);
-        println!("y+vy*t{i}={}+{}*t{i},", y, vy);
-        println!("z+vz*t{i}={}+{}*t{i},", z, vz);
+        let (x, y, z) = s.position;
+        let (vx, vy, vz) = s.velocity;
+        trace.record("part2.equations", || {
+            vec![
+                ("stone", i.to_string()),
+                ("x", format!("x+vx*t{i}={x}+{vx}*t{i}")),
+                ("y", format!("y+vy*t{i}={y}+{vy}*t{i}")),
+                ("z", format!("z+vz*t{i}={z}+{vz}*t{i}")),
+            ]
+        });
     }
 
     let result = solve_with_z3(stones);
@@ -152,12 +160,18 @@ fn part2(stones: &[Hailstone]) -> Result<isize> {
 }
 
 fn main() -> Result<()> {
+    let explain = Explain::from_args()?;
+    let mut trace = Trace::new(explain.is_some());
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
     let stones = parse_input(input)?;
     part1(&stones, 200000000000000, 400000000000000)?;
-    part2(&stones)?;
+    part2(&stones, &mut trace)?;
+    if let Some(explain) = explain {
+        writeln!(io::stdout(), "{}", trace.report(explain))?;
+    }
     Ok(())
 }
 
@@ -170,7 +184,12 @@ fn example_input() {
 20, 19, 15 @  1, -5, -3";
     let stones = parse_input(input).unwrap();
     assert_eq!(part1(&stones, 7, 27).unwrap(), 2);
-    assert_eq!(part2(&stones).unwrap(), 47);
+    let mut trace = Trace::new(true);
+    assert_eq!(part2(&stones, &mut trace).unwrap(), 47);
+    assert_eq!(
+        trace.report(Explain::Text).lines().next(),
+        Some("part2.equations stone=0 x=x+vx*t0=19+-2*t0 y=y+vy*t0=13+1*t0 z=z+vz*t0=30+-2*t0")
+    );
 }
 
 #[test]
@@ -181,5 +200,8 @@ fn real_input() {
         part1(&stones, 200000000000000, 400000000000000).unwrap(),
         21785
     );
-    assert_eq!(part2(&stones).unwrap(), 554668916217145);
+    assert_eq!(
+        part2(&stones, &mut Trace::default()).unwrap(),
+        554668916217145
+    );
 }
@@ -0,0 +1,71 @@
+// the overflow-checked helpers of the `checked` feature shared by the days, included with
+// `#[path = "../../common/checked.rs"] mod checked;` next to the day's own `Int`
+#![allow(dead_code)] // not every day needs every operation
+
+use std::error::Error;
+use std::fmt;
+
+use super::Int;
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Debug)]
+pub struct Overflow {
+    op: char,
+    lhs: Int,
+    rhs: Int,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "arithmetic overflow: {} {} {}",
+            self.lhs, self.op, self.rhs
+        )
+    }
+}
+
+impl Error for Overflow {}
+
+// with `--features checked` overflow is an error instead of wrapping in release builds
+pub fn add(lhs: Int, rhs: Int) -> Result<Int> {
+    if cfg!(feature = "checked") {
+        lhs.checked_add(rhs)
+            .ok_or_else(|| Overflow { op: '+', lhs, rhs }.into())
+    } else {
+        Ok(lhs + rhs)
+    }
+}
+
+pub fn sub(lhs: Int, rhs: Int) -> Result<Int> {
+    if cfg!(feature = "checked") {
+        lhs.checked_sub(rhs)
+            .ok_or_else(|| Overflow { op: '-', lhs, rhs }.into())
+    } else {
+        Ok(lhs - rhs)
+    }
+}
+
+pub fn mul(lhs: Int, rhs: Int) -> Result<Int> {
+    if cfg!(feature = "checked") {
+        lhs.checked_mul(rhs)
+            .ok_or_else(|| Overflow { op: '*', lhs, rhs }.into())
+    } else {
+        Ok(lhs * rhs)
+    }
+}
+
+#[cfg(feature = "checked")]
+#[test]
+fn checked_helpers() {
+    assert_eq!(add(2, 3).unwrap(), 5);
+    assert_eq!(mul(Int::MAX, 1).unwrap(), Int::MAX);
+    let err = add(Int::MAX, 1).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("arithmetic overflow: {} + 1", Int::MAX)
+    );
+    assert!(sub(Int::MIN, 1).unwrap_err().is::<Overflow>());
+    assert!(mul(Int::MAX, 2).unwrap_err().is::<Overflow>());
+}
@@ -0,0 +1,352 @@
+#![allow(dead_code)] // not every day reads every accessor
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Position};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::Frame;
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+// the most steps a single `r` or `c` takes, so a simulation that never finishes and a
+// breakpoint that never hits still hand control back
+pub const MAX_RUN: usize = 1_000_000;
+
+// a step-driven simulation that can be driven by `--debug`
+pub trait Simulation {
+    // advance one step, false once there is nothing left to simulate
+    fn step(&mut self) -> bool;
+    fn render(&self) -> String;
+    // named values that breakpoints can test
+    fn watch(&self, name: &str) -> Option<usize>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    Le,
+    Lt,
+}
+
+impl Op {
+    fn holds(self, lhs: usize, rhs: usize) -> bool {
+        match self {
+            Op::Gt => lhs > rhs,
+            Op::Ge => lhs >= rhs,
+            Op::Eq => lhs == rhs,
+            Op::Ne => lhs != rhs,
+            Op::Le => lhs <= rhs,
+            Op::Lt => lhs < rhs,
+        }
+    }
+}
+
+impl FromStr for Op {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            ">" => Ok(Op::Gt),
+            ">=" => Ok(Op::Ge),
+            "==" => Ok(Op::Eq),
+            "!=" => Ok(Op::Ne),
+            "<=" => Ok(Op::Le),
+            "<" => Ok(Op::Lt),
+            _ => Err(format!("unknown operator {s:?}, expect >, >=, ==, !=, <= or <").into()),
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Eq => "==",
+            Op::Ne => "!=",
+            Op::Le => "<=",
+            Op::Lt => "<",
+        };
+        write!(f, "{op}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    name: String,
+    op: Op,
+    value: usize,
+}
+
+impl FromStr for Breakpoint {
+    type Err = Box<dyn Error>;
+
+    // `name` for `name != 0`, or `name OP k` with any `Op`
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            [name] => Ok(Breakpoint {
+                name: name.to_string(),
+                op: Op::Ne,
+                value: 0,
+            }),
+            [name, op, value] => Ok(Breakpoint {
+                name: name.to_string(),
+                op: op.parse()?,
+                value: value.parse()?,
+            }),
+            _ => Err(format!("unable to parse breakpoint: {s:?}").into()),
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.name, self.op, self.value)
+    }
+}
+
+impl Breakpoint {
+    fn hit(&self, sim: &impl Simulation) -> bool {
+        sim.watch(&self.name)
+            .is_some_and(|v| self.op.holds(v, self.value))
+    }
+}
+
+// whether the driver keeps going after a command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+pub struct Debugger<S> {
+    pub sim: S,
+    breakpoints: Vec<Breakpoint>,
+    steps: usize,
+    finished: bool,
+    message: String,
+    // the command being typed
+    input: String,
+}
+
+impl<S: Simulation> Debugger<S> {
+    pub fn new(sim: S) -> Self {
+        Debugger {
+            sim,
+            breakpoints: vec![],
+            steps: 0,
+            finished: false,
+            message: String::new(),
+            input: String::new(),
+        }
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    // `s` (or empty) step, `r N` run N steps, `c` continue to a breakpoint, `b COND` add a
+    // breakpoint, `d` delete all breakpoints, `q` quit
+    pub fn command(&mut self, line: &str) -> Flow {
+        let line = line.trim();
+        let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+        match (command, arg.trim()) {
+            ("" | "s", _) => self.run(1),
+            ("r", n) => match n.parse() {
+                Ok(n) if n <= MAX_RUN => self.run(n),
+                Ok(_) => self.message = format!("run at most {MAX_RUN} steps at a time"),
+                Err(e) => self.message = format!("unable to parse step count {n:?}: {e}"),
+            },
+            ("c", _) if self.breakpoints.is_empty() => {
+                self.message = "nothing to continue to, add a breakpoint with b".to_string();
+            }
+            ("c", _) => self.run(MAX_RUN),
+            ("b", cond) => match cond.parse::<Breakpoint>() {
+                Ok(b) => {
+                    self.message = format!("breakpoint {} added: {b}", self.breakpoints.len());
+                    self.breakpoints.push(b);
+                }
+                Err(e) => self.message = e.to_string(),
+            },
+            ("d", _) => {
+                self.breakpoints.clear();
+                self.message = "breakpoints deleted".to_string();
+            }
+            ("q", _) => return Flow::Quit,
+            _ => self.message = format!("unknown command {line:?}"),
+        }
+        Flow::Continue
+    }
+
+    fn run(&mut self, n: usize) {
+        self.message.clear();
+        for _ in 0..n {
+            if self.finished || !self.sim.step() {
+                self.finished = true;
+                return;
+            }
+            self.steps += 1;
+            if let Some(i) = self.breakpoints.iter().position(|b| b.hit(&self.sim)) {
+                self.message = format!("breakpoint {i} hit: {}", self.breakpoints[i]);
+                return;
+            }
+        }
+        if n == MAX_RUN && !self.finished {
+            self.message = format!("stopped after {MAX_RUN} steps without a breakpoint hit");
+        }
+    }
+
+    fn key(&mut self, code: KeyCode) -> Flow {
+        match code {
+            KeyCode::Enter => {
+                let line = std::mem::take(&mut self.input);
+                self.command(&line)
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+                Flow::Continue
+            }
+            KeyCode::Char(c) => {
+                self.input.push(c);
+                Flow::Continue
+            }
+            KeyCode::Esc => Flow::Quit,
+            _ => Flow::Continue,
+        }
+    }
+
+    // the state in a box, the breakpoints and message below it and the command line last
+    fn draw(&self, frame: &mut Frame) {
+        let [state, status, message, prompt] = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let title = format!(
+            " step {}{} ",
+            self.steps,
+            if self.finished { " (finished)" } else { "" }
+        );
+        let render = self.sim.render();
+        frame.render_widget(
+            Paragraph::new(render.as_str()).block(Block::bordered().title(title)),
+            state,
+        );
+        let breakpoints: Vec<_> = self.breakpoints.iter().map(|b| b.to_string()).collect();
+        let status_line = match breakpoints.is_empty() {
+            true => "no breakpoints".to_string(),
+            false => format!("breakpoints: {}", breakpoints.join(", ")),
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+        frame.render_widget(Paragraph::new(self.message.as_str()), message);
+        frame.render_widget(Paragraph::new(format!("> {}", self.input)), prompt);
+        frame.set_cursor_position(Position::new(
+            prompt.x + 2 + self.input.chars().count() as u16,
+            prompt.y,
+        ));
+    }
+}
+
+// the simulation in a full screen terminal ui until `q` or Esc
+pub fn debug(sim: impl Simulation) -> Result<()> {
+    if !io::stdout().is_terminal() {
+        return Err("--debug needs a terminal".into());
+    }
+    let mut debugger = Debugger::new(sim);
+    let mut terminal = ratatui::init();
+    let result = (|| -> Result<()> {
+        loop {
+            terminal.draw(|frame| debugger.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && debugger.key(key.code) == Flow::Quit {
+                    return Ok(());
+                }
+            }
+        }
+    })();
+    ratatui::restore();
+    result
+}
+
+#[test]
+fn debugger_commands() {
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    // counts up to a limit
+    struct Counter(usize, usize);
+
+    impl Simulation for Counter {
+        fn step(&mut self) -> bool {
+            self.0 += 1;
+            self.0 <= self.1
+        }
+
+        fn render(&self) -> String {
+            format!("count: {}", self.0)
+        }
+
+        fn watch(&self, name: &str) -> Option<usize> {
+            (name == "count").then_some(self.0)
+        }
+    }
+
+    for bad in ["count =~ 3", "count > x", "count >", ""] {
+        assert!(bad.parse::<Breakpoint>().is_err(), "{bad}");
+    }
+
+    let mut debugger = Debugger::new(Counter(0, usize::MAX));
+    debugger.command("c");
+    assert_eq!(debugger.steps(), 0);
+    assert!(debugger.message().contains("add a breakpoint"));
+    for command in ["s", "r 2", "b count >= 5", "c"] {
+        assert_eq!(debugger.command(command), Flow::Continue);
+    }
+    assert_eq!(debugger.steps(), 5);
+    assert_eq!(debugger.message(), "breakpoint 0 hit: count >= 5");
+    // a breakpoint that never hits stops at the cap
+    debugger.command("d");
+    debugger.command("b count == 0");
+    debugger.command("c");
+    assert_eq!(debugger.steps(), 5 + MAX_RUN);
+    assert!(debugger.message().starts_with("stopped after"));
+    assert_eq!(debugger.command("q"), Flow::Quit);
+
+    let mut debugger = Debugger::new(Counter(0, 2));
+    for c in "r 9".chars() {
+        debugger.key(KeyCode::Char(c));
+    }
+    debugger.key(KeyCode::Enter);
+    assert!(debugger.finished());
+    let mut terminal = Terminal::new(TestBackend::new(30, 6)).unwrap();
+    terminal.draw(|frame| debugger.draw(frame)).unwrap();
+    let screen: Vec<String> = terminal
+        .backend()
+        .buffer()
+        .content()
+        .chunks(30)
+        .map(|row| row.iter().map(|c| c.symbol()).collect())
+        .collect();
+    assert!(screen[0].contains("step 2 (finished)"));
+    assert!(screen[1].contains("count: 3"));
+    assert_eq!(screen[3].trim_end(), "no breakpoints");
+    assert_eq!(screen[5].trim_end(), ">");
+}
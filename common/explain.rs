@@ -0,0 +1,155 @@
+// the opt-in `--explain` trace shared by the days, included with
+// `#[path = "../../common/explain.rs"] mod explain;`
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Explain {
+    Text,
+    Json,
+}
+
+impl Explain {
+    // `--explain` or `--explain=text` prints the trace as text, `--explain=json` as json
+    pub fn from_arg(arg: &str) -> Option<Self> {
+        match arg {
+            "--explain" | "--explain=text" => Some(Explain::Text),
+            "--explain=json" => Some(Explain::Json),
+            _ => None,
+        }
+    }
+
+    // for the days whose only arguments are the explain flags
+    #[allow(dead_code)]
+    pub fn from_args() -> Result<Option<Self>, String> {
+        let mut explain = None;
+        for arg in std::env::args().skip(1) {
+            match Explain::from_arg(&arg) {
+                Some(e) => explain = Some(e),
+                None => {
+                    return Err(format!(
+                        "unknown argument {arg:?}, expect --explain[=text|json]"
+                    ))
+                }
+            }
+        }
+        Ok(explain)
+    }
+}
+
+// key intermediate results of a solver, only recorded when explain mode is on
+#[derive(Debug, Default)]
+pub struct Trace {
+    enabled: bool,
+    steps: Vec<(&'static str, Vec<(&'static str, String)>)>,
+}
+
+impl Trace {
+    pub fn new(enabled: bool) -> Self {
+        Trace {
+            enabled,
+            steps: vec![],
+        }
+    }
+
+    // for the days that skip a whole traced pass when nothing is recorded
+    #[allow(dead_code)]
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn record(
+        &mut self,
+        step: &'static str,
+        fields: impl FnOnce() -> Vec<(&'static str, String)>,
+    ) {
+        if self.enabled {
+            self.steps.push((step, fields()));
+        }
+    }
+
+    // one line per step, or a json array with an object per step; integers of any size and
+    // booleans are json literals, everything else is a string
+    pub fn report(&self, explain: Explain) -> String {
+        let lines = self.steps.iter().map(|(step, fields)| match explain {
+            Explain::Text => fields
+                .iter()
+                .fold(step.to_string(), |s, (k, v)| format!("{s} {k}={v}")),
+            Explain::Json => {
+                let mut object = format!("{{\"step\":{}", json_string(step));
+                for (k, v) in fields {
+                    let v = match is_integer(v) || v == "true" || v == "false" {
+                        true => v.clone(),
+                        false => json_string(v),
+                    };
+                    write!(object, ",{}:{v}", json_string(k)).unwrap();
+                }
+                object + "}"
+            }
+        });
+        let lines: Vec<_> = lines.collect();
+        match explain {
+            Explain::Text => lines.join("\n"),
+            Explain::Json => format!("[{}]", lines.join(",")),
+        }
+    }
+}
+
+// `-?(0|[1-9][0-9]*)`, the integers of the json number grammar
+fn is_integer(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    match digits.as_bytes() {
+        [b'0'] => true,
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    }
+}
+
+// see: https://www.rfc-editor.org/rfc/rfc8259#section-7
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c < ' ' => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[test]
+fn trace_report() {
+    let mut trace = Trace::new(true);
+    trace.record("a", || {
+        vec![
+            ("big", u128::MAX.to_string()),
+            ("neg", "-12".to_string()),
+            ("zero", "0".to_string()),
+            ("padded", "007".to_string()),
+            ("range", "1..3".to_string()),
+            ("flag", "true".to_string()),
+            ("name", "é\u{1b}\"\\\n".to_string()),
+        ]
+    });
+    trace.record("b", Vec::new);
+    assert_eq!(
+        trace.report(Explain::Json),
+        format!(
+            r#"[{{"step":"a","big":{},"neg":-12,"zero":0,"padded":"007","range":"1..3","flag":true,"name":"é\u001b\"\\\n"}},{{"step":"b"}}]"#,
+            u128::MAX
+        )
+    );
+    assert_eq!(trace.report(Explain::Text).lines().last(), Some("b"));
+
+    let mut off = Trace::default();
+    off.record("a", || unreachable!());
+    assert_eq!(off.report(Explain::Json), "[]");
+    assert_eq!(Explain::from_arg("--explain=json"), Some(Explain::Json));
+    assert_eq!(Explain::from_arg("--explain=yaml"), None);
+}
@@ -0,0 +1,19 @@
+// a seeded xorshift64 generator for the randomized tests, deterministic and dependency free
+// see: https://en.wikipedia.org/wiki/Xorshift
+pub struct XorShift(u64);
+
+impl XorShift {
+    // a zero seed would only ever produce zero
+    pub fn new(seed: u64) -> Self {
+        assert_ne!(seed, 0, "xorshift needs a non-zero seed");
+        XorShift(seed)
+    }
+
+    // a value in 0..n
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % n
+    }
+}